@@ -0,0 +1,232 @@
+use std::path::Path;
+
+use git2::{Oid, Repository};
+use semifold_resolver::{
+    context::Context,
+    conventional::{ConventionalCommit, commit_changeset},
+    error::ResolveError,
+    utils::{commits_touching, release_tag_prefix},
+};
+
+use crate::{
+    compare_links, create_pr_resolver, generate_changelog_at,
+    utils::{CommitIndex, CommitInfo, empty_changelog, insert_changelog_entry},
+};
+
+/// A release tag of a package, named `<package>-v<version>`.
+#[derive(Debug)]
+pub struct ReleaseTag {
+    pub name: String,
+    pub version: semver::Version,
+    pub commit: Oid,
+    /// Commit time in seconds since the Unix epoch.
+    pub time: i64,
+}
+
+/// Find the release tags of a package, sorted from the oldest version.
+pub fn find_release_tags(
+    repo: &Repository,
+    package_name: &str,
+) -> Result<Vec<ReleaseTag>, git2::Error> {
+    let prefix = release_tag_prefix(package_name);
+    let mut tags = repo
+        .tag_names(Some(&format!("{prefix}*")))?
+        .iter()
+        .flatten()
+        .filter_map(|name| {
+            let version = semver::Version::parse(name.strip_prefix(&prefix)?).ok()?;
+            let commit = repo
+                .revparse_single(&format!("refs/tags/{name}"))
+                .and_then(|o| o.peel_to_commit())
+                .ok()?;
+            Some(ReleaseTag {
+                name: name.to_string(),
+                version,
+                commit: commit.id(),
+                time: commit.time().seconds(),
+            })
+        })
+        .collect::<Vec<_>>();
+    tags.sort_by(|a, b| a.version.cmp(&b.version));
+    Ok(tags)
+}
+
+/// Commits touching `path` reachable from `to` but not from `from`.
+fn commits_between(
+    repo: &Repository,
+    from: Option<Oid>,
+    to: Oid,
+    path: &Path,
+) -> Result<Vec<CommitInfo>, git2::Error> {
+    commits_touching(repo, from, to, path)?
+        .into_iter()
+        .map(|oid| {
+            let commit = repo.find_commit(oid)?;
+            Ok(CommitInfo {
+                oid,
+                message: commit.message().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+            })
+        })
+        .collect()
+}
+
+/// Rebuild the full changelog of a package from its release tags, grouping
+/// the commits touching the package between consecutive tags.
+///
+/// Returns the changelog content and the number of releases.
+pub async fn backfill_changelog(
+    ctx: &Context,
+    repo: &Repository,
+    package_name: &str,
+    conventional: bool,
+) -> Result<(String, usize), ResolveError> {
+    let config = ctx.config.as_ref().unwrap();
+    let root = ctx.repo_root.as_ref().unwrap();
+    let package_config = config.packages.get(package_name).unwrap();
+    let format = config.changelog.format;
+    let links = compare_links(ctx, package_name);
+
+    let git_error = |e: git2::Error| ResolveError::GitError {
+        message: e.message().to_string(),
+    };
+    let tags = find_release_tags(repo, package_name).map_err(git_error)?;
+    let mut releases = Vec::new();
+    let mut commit_index = CommitIndex::default();
+    let mut previous = None;
+    for tag in &tags {
+        let commits =
+            commits_between(repo, previous, tag.commit, &package_config.path).map_err(git_error)?;
+        let changesets = commits
+            .iter()
+            .map(|commit| {
+                let parsed = conventional
+                    .then(|| ConventionalCommit::parse(&commit.message))
+                    .flatten();
+                commit_changeset(
+                    ctx,
+                    commit.oid.to_string(),
+                    root,
+                    package_name,
+                    commit.oid,
+                    &commit.message,
+                    parsed.as_ref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        for commit in commits {
+            commit_index.insert_commit(commit);
+        }
+        releases.push((tag, changesets));
+        previous = Some(tag.commit);
+    }
+
+    let mut pr_resolver = create_pr_resolver(ctx);
+    if let Some(pr_resolver) = pr_resolver.as_mut() {
+        pr_resolver.prefetch(commit_index.commits()).await?;
+    }
+
+    let mut content = empty_changelog(format);
+    for (tag, changesets) in &releases {
+        let date = chrono::DateTime::from_timestamp(tag.time, 0)
+            .unwrap_or_default()
+            .format("%Y-%m-%d")
+            .to_string();
+        let entry = generate_changelog_at(
+            ctx,
+            &commit_index,
+            pr_resolver.as_mut(),
+            changesets,
+            package_name,
+            &tag.version.to_string(),
+            &date,
+        )
+        .await?;
+        content = insert_changelog_entry(&content, &entry, format, links.as_ref())
+            .expect("new changelogs have a header");
+    }
+
+    Ok((content, releases.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use semifold_resolver::utils::release_tag_name;
+
+    use super::*;
+    use crate::{
+        parser::read_changelog,
+        read_latest_changelog,
+        test_utils::{self, block_on, commit, temp_repo},
+    };
+
+    fn tag(repo: &Repository, version: &str) {
+        let head = repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap();
+        repo.tag_lightweight(&release_tag_name("a", version), &head, false)
+            .unwrap();
+    }
+
+    #[test]
+    fn backfills_parsable_changelog_from_release_tags() {
+        let (root, repo) = temp_repo("backfill");
+        commit(
+            &repo,
+            &[("a/src/lib.rs", Some("parser"))],
+            "feat: add parser",
+        );
+        commit(
+            &repo,
+            &[("b/src/lib.rs", Some("b"))],
+            "feat: unrelated package",
+        );
+        tag(&repo, "1.0.0");
+        commit(
+            &repo,
+            &[("a/src/lib.rs", Some("empty input"))],
+            "fix: handle empty input (#3)",
+        );
+        tag(&repo, "1.0.1");
+        commit(
+            &repo,
+            &[("a/src/lib.rs", Some("unreleased"))],
+            "feat: unreleased",
+        );
+
+        let config = test_utils::config(serde_json::json!({
+            "packages": { "a": { "path": "a", "resolver": "rust" } },
+            "tags": { "feat": "New Features", "fix": "Bug Fixes" },
+        }));
+        let ctx = Context {
+            config: Some(config),
+            repo_root: Some(root.clone()),
+            ..Default::default()
+        };
+
+        let (content, releases) = block_on(backfill_changelog(&ctx, &repo, "a", true)).unwrap();
+        assert_eq!(releases, 2);
+        let path = root.join("a/CHANGELOG.md");
+        std::fs::write(&path, content).unwrap();
+
+        let changelog = read_changelog(&path).unwrap();
+        let versions = changelog
+            .releases
+            .iter()
+            .map(|r| r.version.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, ["1.0.1", "1.0.0"]);
+        assert_eq!(
+            changelog.releases[0].sections[0].title.as_deref(),
+            Some("Bug Fixes")
+        );
+        assert_eq!(
+            changelog.releases[0].sections[0].entries,
+            ["handle empty input (#3)"]
+        );
+        assert_eq!(changelog.releases[1].sections[0].entries, ["add parser"]);
+
+        let latest = block_on(read_latest_changelog(&path)).unwrap();
+        assert_eq!(latest.version, "v1.0.1");
+        assert!(latest.body.contains("handle empty input"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
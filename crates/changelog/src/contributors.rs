@@ -0,0 +1,264 @@
+use std::collections::{HashMap, HashSet};
+
+use git2::{Oid, Repository};
+use semifold_resolver::error::ResolveError;
+
+use crate::{github::PrResolver, utils::PrInfo};
+
+/// A change attributed to a contributor.
+#[derive(Debug, Clone)]
+pub struct Contribution {
+    /// GitHub username of the contributor.
+    pub login: String,
+    /// Introducing commit, only set when the contributor is its sole author.
+    pub commit: Option<Oid>,
+    pub pr: Option<PrInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Contributor {
+    pub login: String,
+    /// Whether the release contains the first contribution of the user.
+    pub is_new: bool,
+    /// First pull request of the user in the release.
+    pub first_pr: Option<PrInfo>,
+}
+
+/// GitHub login of the author of a commit, for commits without pull request
+/// information.
+///
+/// The login is taken from a `users.noreply.github.com` email, falling back
+/// to an author name which is a valid login.
+pub fn commit_author_login(repo: &Repository, oid: Oid) -> Option<String> {
+    let commit = repo.find_commit(oid).ok()?;
+    let author = commit.author();
+    let noreply = author
+        .email()
+        .and_then(|email| email.strip_suffix("@users.noreply.github.com"))
+        .map(|user| user.rsplit('+').next().unwrap_or(user));
+    noreply
+        .or(author.name())
+        .filter(|login| is_login(login))
+        .map(String::from)
+}
+
+/// Check whether the string is a valid GitHub username.
+pub fn is_login(s: &str) -> bool {
+    !s.is_empty() && !s.starts_with('-') && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Find the oldest commit of each author email, walking the history from
+/// `HEAD` until all of them are found.
+fn first_commits(
+    repo: &Repository,
+    emails: &HashSet<String>,
+) -> Result<HashMap<String, Oid>, git2::Error> {
+    let mut first = HashMap::new();
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        let Some(email) = commit.author().email().map(str::to_lowercase) else {
+            continue;
+        };
+        if emails.contains(&email) {
+            first.entry(email).or_insert(oid);
+            if first.len() == emails.len() {
+                break;
+            }
+        }
+    }
+    Ok(first)
+}
+
+/// Check locally whether a contributor authored a commit before `commit`,
+/// using the author emails of their commits in the release.
+fn is_new_locally(
+    repo: &Repository,
+    first_commits: &HashMap<String, Oid>,
+    emails: &HashSet<String>,
+    commit: Oid,
+) -> bool {
+    !emails.iter().any(|email| {
+        first_commits.get(email).is_some_and(|&first| {
+            first != commit && repo.graph_descendant_of(commit, first).unwrap_or(false)
+        })
+    })
+}
+
+/// List the contributors of a release in order of their contributions.
+///
+/// New contributors are detected with the local git history, the GitHub API
+/// is only asked about contributors without commits in the release.
+pub async fn find_contributors(
+    repo: Option<&Repository>,
+    pr_resolver: Option<&mut PrResolver>,
+    contributions: &[Contribution],
+) -> Result<Vec<Contributor>, ResolveError> {
+    let git_error = |e: git2::Error| ResolveError::GitError {
+        message: e.message().to_string(),
+    };
+
+    // Author emails and the earliest commit of each contributor
+    let mut identities: HashMap<&str, (HashSet<String>, Oid)> = HashMap::new();
+    if let Some(repo) = repo {
+        for contribution in contributions {
+            let Some(oid) = contribution.commit else {
+                continue;
+            };
+            let commit = repo.find_commit(oid).map_err(git_error)?;
+            let Some(email) = commit.author().email().map(str::to_lowercase) else {
+                continue;
+            };
+            identities
+                .entry(&contribution.login)
+                .or_insert_with(|| (HashSet::new(), oid))
+                .0
+                .insert(email);
+        }
+    }
+    let first_commits = match repo {
+        Some(repo) if !identities.is_empty() => {
+            let emails = identities
+                .values()
+                .flat_map(|(emails, _)| emails.iter().cloned())
+                .collect();
+            first_commits(repo, &emails).map_err(git_error)?
+        }
+        _ => HashMap::new(),
+    };
+
+    let mut contributors: Vec<Contributor> = Vec::new();
+    for contribution in contributions {
+        match contributors
+            .iter_mut()
+            .find(|c| c.login == contribution.login)
+        {
+            Some(contributor) => {
+                if contributor.first_pr.is_none() {
+                    contributor.first_pr = contribution.pr.clone();
+                }
+            }
+            None => contributors.push(Contributor {
+                login: contribution.login.clone(),
+                is_new: false,
+                first_pr: contribution.pr.clone(),
+            }),
+        }
+    }
+
+    let mut unknown = Vec::new();
+    for contributor in &mut contributors {
+        match (repo, identities.get(contributor.login.as_str())) {
+            (Some(repo), Some((emails, commit))) => {
+                contributor.is_new = is_new_locally(repo, &first_commits, emails, *commit);
+            }
+            _ => unknown.push(contributor.login.clone()),
+        }
+    }
+
+    if let Some(pr_resolver) = pr_resolver
+        && !unknown.is_empty()
+    {
+        let counts = pr_resolver.merged_pr_counts(&unknown).await?;
+        for contributor in &mut contributors {
+            let Some(&count) = counts.get(&contributor.login) else {
+                continue;
+            };
+            // Merged pull requests include the ones of this release
+            let release_prs = contributions
+                .iter()
+                .filter(|c| c.login == contributor.login)
+                .filter_map(|c| c.pr.as_ref().map(|pr| pr.number))
+                .collect::<HashSet<_>>()
+                .len() as u64;
+            contributor.is_new = release_prs > 0 && count <= release_prs;
+        }
+    }
+
+    Ok(contributors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{block_on, commit_as, temp_repo};
+
+    fn commit(repo: &Repository, email: &str, message: &str) -> Oid {
+        commit_as(repo, "user", email, &[], message)
+    }
+
+    fn contribution(login: &str, commit: Option<Oid>) -> Contribution {
+        Contribution {
+            login: login.to_string(),
+            commit,
+            pr: None,
+        }
+    }
+
+    #[test]
+    fn detects_new_contributors_locally() {
+        let (dir, repo) = temp_repo("contributors");
+
+        commit(&repo, "alice@example.com", "init");
+        let alice = commit(&repo, "Alice@example.com", "feat: alice");
+        let bob = commit(&repo, "bob@example.com", "feat: bob");
+        let bob_again = commit(&repo, "bob@example.com", "fix: bob");
+
+        let contributors = block_on(find_contributors(
+            Some(&repo),
+            None,
+            &[
+                contribution("alice", Some(alice)),
+                contribution("bob", Some(bob)),
+                contribution("bob", Some(bob_again)),
+                contribution("carol", None),
+            ],
+        ))
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let summary = contributors
+            .iter()
+            .map(|c| (c.login.as_str(), c.is_new))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, [("alice", false), ("bob", true), ("carol", false)]);
+    }
+
+    #[test]
+    fn derives_login_from_commit_author() {
+        let (dir, repo) = temp_repo("author-login");
+
+        let noreply = commit_as(
+            &repo,
+            "Dave Doe",
+            "12345+dave@users.noreply.github.com",
+            &[],
+            "feat: dave",
+        );
+        let legacy_noreply = commit_as(
+            &repo,
+            "Erin",
+            "erin@users.noreply.github.com",
+            &[],
+            "feat: erin",
+        );
+        let named = commit_as(&repo, "frank-f", "frank@example.com", &[], "feat: frank");
+        let unnamed = commit_as(
+            &repo,
+            "Grace Hopper",
+            "grace@example.com",
+            &[],
+            "feat: grace",
+        );
+
+        let login = |oid| commit_author_login(&repo, oid);
+        assert_eq!(login(noreply).as_deref(), Some("dave"));
+        assert_eq!(login(legacy_noreply).as_deref(), Some("erin"));
+        assert_eq!(login(named).as_deref(), Some("frank-f"));
+        assert_eq!(login(unnamed), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
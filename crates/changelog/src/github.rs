@@ -0,0 +1,572 @@
+use std::{collections::HashMap, sync::LazyLock, time::Duration};
+
+use regex::Regex;
+use reqwest::{RequestBuilder, StatusCode, header::HeaderMap};
+use semifold_resolver::error::ResolveError;
+use serde_json::{Value, json};
+
+use crate::{
+    contributors::is_login,
+    utils::{CommitInfo, PrInfo},
+};
+
+/// Default GitHub GraphQL endpoint.
+pub const GITHUB_GRAPHQL_URL: &str = "https://api.github.com/graphql";
+
+/// Default GitHub REST API URL.
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Pull request reference of squash merges in commit messages, e.g. `(#12)`.
+static PR_REFERENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\(#(\d+)\)").unwrap());
+
+/// Number of commits resolved by a single GraphQL request.
+const BATCH_SIZE: usize = 50;
+
+/// Resolve pull requests of commits with batched GraphQL requests.
+///
+/// The GraphQL API rejects anonymous requests, so without a token commits are
+/// looked up one by one with the REST API instead. Results are memoized, so
+/// every commit is looked up at most once per run.
+pub struct PrResolver {
+    client: reqwest::Client,
+    endpoint: String,
+    api_url: String,
+    token: Option<String>,
+    owner: String,
+    repo: String,
+    max_retries: u32,
+    backoff: Duration,
+    cache: HashMap<String, Option<PrInfo>>,
+    merged_counts: HashMap<String, u64>,
+}
+
+impl PrResolver {
+    /// Create a resolver for the given repository.
+    ///
+    /// The endpoints and token are read from `GITHUB_GRAPHQL_URL`,
+    /// `GITHUB_API_URL` and `GITHUB_TOKEN` when set.
+    pub fn new(owner: &str, repo: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: std::env::var("GITHUB_GRAPHQL_URL")
+                .unwrap_or_else(|_| GITHUB_GRAPHQL_URL.to_string()),
+            api_url: std::env::var("GITHUB_API_URL").unwrap_or_else(|_| GITHUB_API_URL.to_string()),
+            token: std::env::var("GITHUB_TOKEN").ok(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            max_retries: 3,
+            backoff: Duration::from_secs(1),
+            cache: HashMap::new(),
+            merged_counts: HashMap::new(),
+        }
+    }
+
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Set the base URL of the REST API used without a token.
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Set the retry limit and the base delay used when no `retry-after`
+    /// header is returned on rate limiting.
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Resolve pull requests of all commits not looked up yet.
+    pub async fn prefetch<'a>(
+        &mut self,
+        commits: impl IntoIterator<Item = &'a CommitInfo>,
+    ) -> Result<(), ResolveError> {
+        let mut pending = Vec::new();
+        for commit in commits {
+            let oid = commit.oid.to_string();
+            if !self.cache.contains_key(&oid)
+                && !pending.iter().any(|c: &&CommitInfo| c.oid == commit.oid)
+            {
+                pending.push(commit);
+            }
+        }
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        if self.token.is_none() {
+            log::debug!("GITHUB_TOKEN is not set, resolving pull requests anonymously");
+            for commit in pending {
+                let pr_info = self.query_rest(commit).await?;
+                self.cache.insert(commit.oid.to_string(), pr_info);
+            }
+            return Ok(());
+        }
+
+        for batch in pending.chunks(BATCH_SIZE) {
+            let data = self.query_batch(batch).await?;
+            for (idx, commit) in batch.iter().enumerate() {
+                let pr_info =
+                    parse_associated_pr(&data, idx).or_else(|| parse_pr(&data[format!("p{idx}")]));
+                self.cache.insert(commit.oid.to_string(), pr_info);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the pull request of a commit, querying it when not cached.
+    pub async fn get(&mut self, commit: &CommitInfo) -> Result<Option<PrInfo>, ResolveError> {
+        self.prefetch([commit]).await?;
+        Ok(self.cache.get(&commit.oid.to_string()).cloned().flatten())
+    }
+
+    /// Look up the pull request of a commit with anonymous REST requests.
+    async fn query_rest(&self, commit: &CommitInfo) -> Result<Option<PrInfo>, ResolveError> {
+        let base = format!("{}/repos/{}/{}", self.api_url, self.owner, self.repo);
+        let prs = self
+            .get_json(&format!("{base}/commits/{}/pulls", commit.oid))
+            .await?;
+        if let Some(pr_info) = prs
+            .as_ref()
+            .and_then(|prs| prs.get(0))
+            .and_then(parse_rest_pr)
+        {
+            return Ok(Some(pr_info));
+        }
+
+        // Squash merges reference the pull request in the commit message
+        let Some(pr_number) = PR_REFERENCE
+            .captures(&commit.message)
+            .and_then(|caps| caps[1].parse::<u64>().ok())
+        else {
+            return Ok(None);
+        };
+        let pr = self.get_json(&format!("{base}/pulls/{pr_number}")).await?;
+        Ok(pr.as_ref().and_then(parse_rest_pr))
+    }
+
+    /// Get a REST resource, `None` if it does not exist on GitHub, e.g. for
+    /// commits which are not pushed yet.
+    async fn get_json(&self, url: &str) -> Result<Option<Value>, ResolveError> {
+        let (status, value, text) = self
+            .send_request(|| {
+                self.client
+                    .get(url)
+                    .header("Accept", "application/vnd.github+json")
+            })
+            .await?;
+        match status {
+            _ if status.is_success() => Ok(Some(value)),
+            StatusCode::NOT_FOUND | StatusCode::UNPROCESSABLE_ENTITY => Ok(None),
+            _ => Err(ResolveError::GitHubError {
+                message: format!("Failed to query pull requests: {status} {text}"),
+            }),
+        }
+    }
+
+    async fn query_batch(&self, commits: &[&CommitInfo]) -> Result<Value, ResolveError> {
+        let mut fields = String::new();
+        for (idx, commit) in commits.iter().enumerate() {
+            fields.push_str(&format!(
+                "c{idx}: object(oid: \"{}\") {{ ... on Commit {{ associatedPullRequests(first: 1) {{ nodes {{ ...pr }} }} }} }}\n",
+                commit.oid
+            ));
+            // Squash merges reference the pull request in the commit message
+            if let Some(caps) = PR_REFERENCE.captures(&commit.message)
+                && let Ok(pr_number) = caps[1].parse::<u64>()
+            {
+                fields.push_str(&format!(
+                    "p{idx}: pullRequest(number: {pr_number}) {{ ...pr }}\n"
+                ));
+            }
+        }
+        let query = format!(
+            "query($owner: String!, $repo: String!) {{\n\
+            repository(owner: $owner, name: $repo) {{\n{fields}}}\n\
+            }}\n\
+            fragment pr on PullRequest {{ number url author {{ login }} }}"
+        );
+        let data = self
+            .send(json!({
+                "query": query,
+                "variables": { "owner": self.owner, "repo": self.repo },
+            }))
+            .await?;
+        Ok(data["repository"].clone())
+    }
+
+    /// Count merged pull requests of users in the repository.
+    ///
+    /// Returns an empty map without a token.
+    pub async fn merged_pr_counts(
+        &mut self,
+        logins: &[String],
+    ) -> Result<HashMap<String, u64>, ResolveError> {
+        let mut pending = Vec::new();
+        for login in logins {
+            if is_login(login)
+                && !self.merged_counts.contains_key(login)
+                && !pending.contains(&login)
+            {
+                pending.push(login);
+            }
+        }
+        if self.token.is_none() {
+            return Ok(HashMap::new());
+        }
+
+        for batch in pending.chunks(BATCH_SIZE) {
+            let fields = batch
+                .iter()
+                .enumerate()
+                .map(|(idx, login)| {
+                    format!(
+                        "u{idx}: search(query: \"repo:{}/{} is:pr is:merged author:{login}\", type: ISSUE, first: 1) {{ issueCount }}\n",
+                        self.owner, self.repo
+                    )
+                })
+                .collect::<String>();
+            let data = self
+                .send(json!({ "query": format!("query {{\n{fields}}}") }))
+                .await?;
+            for (idx, login) in batch.iter().enumerate() {
+                if let Some(count) = data[format!("u{idx}")]["issueCount"].as_u64() {
+                    self.merged_counts.insert(login.to_string(), count);
+                }
+            }
+        }
+
+        Ok(logins
+            .iter()
+            .filter_map(|login| Some((login.clone(), *self.merged_counts.get(login)?)))
+            .collect())
+    }
+
+    /// Send a GraphQL request, retrying on rate limits.
+    async fn send(&self, body: Value) -> Result<Value, ResolveError> {
+        let (status, value, text) = self
+            .send_request(|| self.client.post(&self.endpoint).json(&body))
+            .await?;
+        if !status.is_success() || is_graphql_rate_limited(&value) {
+            return Err(ResolveError::GitHubError {
+                message: format!("Failed to query pull requests: {status} {text}"),
+            });
+        }
+        if let Some(errors) = value["errors"].as_array() {
+            log::debug!("GraphQL errors while querying pull requests: {errors:?}");
+        }
+        Ok(value["data"].clone())
+    }
+
+    /// Send a request built by `build`, retrying on rate limits.
+    ///
+    /// Returns the status, the parsed JSON body and the raw body of the last
+    /// response.
+    async fn send_request<F: Fn() -> RequestBuilder>(
+        &self,
+        build: F,
+    ) -> Result<(StatusCode, Value, String), ResolveError> {
+        let mut attempt = 0;
+        loop {
+            let mut request = build().header("User-Agent", "semifold");
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+            let resp = request
+                .send()
+                .await
+                .map_err(|e| ResolveError::GitHubError {
+                    message: format!("Failed to query pull requests: {e}"),
+                })?;
+
+            let status = resp.status();
+            let headers = resp.headers().clone();
+            let text = resp.text().await.map_err(|e| ResolveError::GitHubError {
+                message: format!("Failed to read pull requests response: {e}"),
+            })?;
+            let value = serde_json::from_str::<Value>(&text).unwrap_or(Value::Null);
+
+            if is_rate_limited(status, &headers, &value) && attempt < self.max_retries {
+                let delay = retry_delay(&headers).unwrap_or(self.backoff * 2u32.pow(attempt));
+                log::warn!(
+                    "GitHub rate limit hit, retrying in {}s ({}/{})",
+                    delay.as_secs(),
+                    attempt + 1,
+                    self.max_retries
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+            return Ok((status, value, text));
+        }
+    }
+}
+
+fn header_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+}
+
+/// Whether a response is rejected by a rate limit.
+///
+/// `403` is also returned for missing permissions, so it only counts when the
+/// rate limit headers say so.
+fn is_rate_limited(status: StatusCode, headers: &HeaderMap, value: &Value) -> bool {
+    let forbidden_by_limit = status == StatusCode::FORBIDDEN
+        && (headers.contains_key("retry-after")
+            || header_number(headers, "x-ratelimit-remaining") == Some(0));
+    status == StatusCode::TOO_MANY_REQUESTS || forbidden_by_limit || is_graphql_rate_limited(value)
+}
+
+/// GraphQL reports rate limits as errors of successful responses.
+fn is_graphql_rate_limited(value: &Value) -> bool {
+    value["errors"]
+        .as_array()
+        .is_some_and(|errors| errors.iter().any(|e| e["type"] == "RATE_LIMITED"))
+}
+
+/// Delay requested by the server, from `retry-after` or the rate limit reset.
+fn retry_delay(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| header_number(headers, name);
+    if let Some(seconds) = header("retry-after") {
+        return Some(Duration::from_secs(seconds));
+    }
+    if header("x-ratelimit-remaining") == Some(0)
+        && let Some(reset) = header("x-ratelimit-reset")
+    {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        return Some(Duration::from_secs(reset.saturating_sub(now)));
+    }
+    None
+}
+
+fn parse_pr(value: &Value) -> Option<PrInfo> {
+    Some(PrInfo {
+        number: value["number"].as_u64()?,
+        author: value["author"]["login"].as_str().map(|s| s.to_string()),
+        url: value["url"].as_str().map(|s| s.to_string()),
+    })
+}
+
+/// Parse a pull request of the REST API.
+fn parse_rest_pr(value: &Value) -> Option<PrInfo> {
+    Some(PrInfo {
+        number: value["number"].as_u64()?,
+        author: value["user"]["login"].as_str().map(|s| s.to_string()),
+        url: value["html_url"].as_str().map(|s| s.to_string()),
+    })
+}
+
+fn parse_associated_pr(data: &Value, idx: usize) -> Option<PrInfo> {
+    parse_pr(&data[format!("c{idx}")]["associatedPullRequests"]["nodes"][0])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    use git2::Oid;
+
+    use super::*;
+    use crate::test_utils::block_on;
+
+    /// Serve the scripted responses in order and record the request lines
+    /// and bodies.
+    fn serve(responses: Vec<(u16, &'static str, String)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/graphql", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for (status, headers, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0; 4096];
+                let body_start = loop {
+                    let n = stream.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&buf[..body_start]).to_lowercase();
+                let length = head
+                    .lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .and_then(|l| l.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                while buf.len() < body_start + length {
+                    let n = stream.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let request_line = head.lines().next().unwrap_or_default().to_string();
+                recorded.lock().unwrap().push(format!(
+                    "{request_line}\n{}",
+                    String::from_utf8_lossy(&buf[body_start..])
+                ));
+                let resp = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{headers}\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(resp.as_bytes()).unwrap();
+            }
+        });
+        (endpoint, requests)
+    }
+
+    fn commit(n: u8, message: &str) -> CommitInfo {
+        CommitInfo {
+            oid: Oid::from_bytes(&[n; 20]).unwrap(),
+            message: message.to_string(),
+            time: 0,
+        }
+    }
+
+    fn resolver(endpoint: &str) -> PrResolver {
+        PrResolver::new("owner", "repo")
+            .with_endpoint(endpoint)
+            .with_token(Some("token".to_string()))
+            .with_retries(2, Duration::from_millis(10))
+    }
+
+    #[test]
+    fn batches_and_memoizes_commits() {
+        let body = json!({ "data": { "repository": {
+            "c0": { "associatedPullRequests": { "nodes": [
+                { "number": 1, "url": "https://github.com/owner/repo/pull/1", "author": { "login": "alice" } }
+            ] } },
+            "c1": { "associatedPullRequests": { "nodes": [] } },
+            "p1": { "number": 2, "url": "https://github.com/owner/repo/pull/2", "author": null },
+        } } });
+        let (endpoint, requests) = serve(vec![(200, "", body.to_string())]);
+        let commits = [commit(1, "feat: one"), commit(2, "fix: two (#2)")];
+
+        block_on(async {
+            let mut resolver = resolver(&endpoint);
+            resolver.prefetch(&commits).await.unwrap();
+            let first = resolver.get(&commits[0]).await.unwrap().unwrap();
+            assert_eq!(first.number, 1);
+            assert_eq!(first.author.as_deref(), Some("alice"));
+            let second = resolver.get(&commits[1]).await.unwrap().unwrap();
+            assert_eq!(second.number, 2);
+            assert_eq!(second.author, None);
+        });
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("c1: object"));
+        assert!(requests[0].contains("p1: pullRequest(number: 2)"));
+    }
+
+    #[test]
+    fn retries_after_rate_limit() {
+        let body = json!({ "data": { "repository": {
+            "c0": { "associatedPullRequests": { "nodes": [
+                { "number": 3, "url": null, "author": null }
+            ] } },
+        } } });
+        let (endpoint, requests) = serve(vec![
+            (429, "Retry-After: 0\r\n", "{}".to_string()),
+            (403, "X-RateLimit-Remaining: 0\r\n", "{}".to_string()),
+            (200, "", body.to_string()),
+        ]);
+
+        let pr_info = block_on(resolver(&endpoint).get(&commit(3, "chore"))).unwrap();
+        assert_eq!(pr_info.map(|p| p.number), Some(3));
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn fails_when_retries_are_exhausted() {
+        let (endpoint, _) = serve(vec![
+            (429, "Retry-After: 0\r\n", "{}".to_string()),
+            (429, "Retry-After: 0\r\n", "{}".to_string()),
+            (429, "Retry-After: 0\r\n", "{}".to_string()),
+        ]);
+
+        let result = block_on(resolver(&endpoint).get(&commit(4, "chore")));
+        assert!(matches!(result, Err(ResolveError::GitHubError { .. })));
+    }
+
+    #[test]
+    fn fails_on_forbidden_without_rate_limit() {
+        let (endpoint, requests) = serve(vec![(403, "", "{}".to_string())]);
+
+        let result = block_on(resolver(&endpoint).get(&commit(6, "chore")));
+        assert!(matches!(result, Err(ResolveError::GitHubError { .. })));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn resolves_anonymously_with_rest() {
+        let pr = json!({ "number": 5, "html_url": "https://github.com/owner/repo/pull/5", "user": { "login": "bob" } });
+        let (endpoint, requests) = serve(vec![
+            (200, "", "[]".to_string()),
+            (200, "", pr.to_string()),
+            (422, "", "{}".to_string()),
+        ]);
+        let anonymous = || {
+            PrResolver::new("owner", "repo")
+                .with_api_url(endpoint.trim_end_matches("/graphql"))
+                .with_token(None)
+        };
+
+        let pr_info = block_on(anonymous().get(&commit(5, "fix (#5)")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(pr_info.number, 5);
+        assert_eq!(pr_info.author.as_deref(), Some("bob"));
+        // Commits which are not pushed are not found
+        let pr_info = block_on(anonymous().get(&commit(7, "chore"))).unwrap();
+        assert!(pr_info.is_none());
+
+        let requests = requests.lock().unwrap();
+        let commit_oid = commit(5, "").oid;
+        assert!(requests[0].starts_with(&format!(
+            "get /repos/owner/repo/commits/{commit_oid}/pulls "
+        )));
+        assert!(requests[1].starts_with("get /repos/owner/repo/pulls/5 "));
+    }
+
+    #[test]
+    fn counts_merged_pull_requests() {
+        let body = json!({ "data": {
+            "u0": { "issueCount": 1 },
+            "u1": { "issueCount": 12 },
+        } });
+        let (endpoint, requests) = serve(vec![(200, "", body.to_string())]);
+        let logins = ["alice", "bob", "alice", "not a login"].map(String::from);
+
+        let counts = block_on(async {
+            let mut resolver = resolver(&endpoint);
+            resolver.merged_pr_counts(&logins).await.unwrap();
+            // Memoized counts do not query again
+            resolver.merged_pr_counts(&logins[..2]).await.unwrap()
+        });
+        assert_eq!(counts.get("alice"), Some(&1));
+        assert_eq!(counts.get("bob"), Some(&12));
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("repo:owner/repo is:pr is:merged author:bob"));
+    }
+}
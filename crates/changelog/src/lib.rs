@@ -1,20 +1,33 @@
 use std::{collections::HashMap, path::Path};
 
-use semifold_resolver::{changeset, context, error::ResolveError};
+use semifold_resolver::{
+    changeset,
+    config::{ChangelogConfig, SectionPosition},
+    context,
+    error::ResolveError,
+};
 
-use crate::utils::PrInfo;
+use crate::{
+    contributors::{Contribution, commit_author_login, find_contributors},
+    github::PrResolver,
+    template::{HeaderContext, LineContext, RepoContext, SectionContext, TagContext, Templates},
+    utils::PrInfo,
+};
 
+pub mod backfill;
+pub mod contributors;
+pub mod github;
+pub mod parser;
+pub mod template;
+#[cfg(test)]
+mod test_utils;
 pub mod types;
 pub mod utils;
 
-pub fn format_line(
-    changeset: &changeset::Changeset,
+fn format_commit_link(
     repo_info: &Option<context::RepoInfo>,
-    pr_info: &Option<PrInfo>,
     commit_hash: &Option<String>,
 ) -> String {
-    let mut line = String::from("- ");
-
     if let Some(repo_info) = repo_info.as_ref()
         && let Some(commit_hash) = commit_hash
     {
@@ -22,139 +35,766 @@ pub fn format_line(
             "https://github.com/{}/{}/commit/{}",
             repo_info.owner, repo_info.repo_name, commit_hash
         );
-        line.push_str(&format!("[`{}`]({}): ", &commit_hash[..7], commit_url));
+        format!("[`{}`]({}): ", &commit_hash[..7], commit_url)
+    } else {
+        String::new()
     }
-    line.push_str(&changeset.summary);
+}
 
-    if let Some(pr_info) = pr_info.as_ref() {
-        if let Some(url) = pr_info.url.as_ref() {
-            line.push_str(&format!(" ([#{}]({url})", pr_info.number));
-        } else {
-            line.push_str(&format!(" (#{}", pr_info.number));
-        }
-        if let Some(author) = pr_info.author.as_ref() {
-            line.push_str(&format!(" by @{}", author));
-        }
-        line.push(')');
+/// Format a change line with the default line template.
+pub fn format_line(
+    changeset: &changeset::Changeset,
+    repo_info: &Option<context::RepoInfo>,
+    pr_info: &Option<PrInfo>,
+    commit_hash: &Option<String>,
+) -> Result<String, ResolveError> {
+    Templates::default().render_line(&LineContext::new(
+        changeset,
+        repo_info,
+        pr_info,
+        commit_hash,
+    ))
+}
+
+/// Format the breaking change note of a changeset, if any.
+pub fn format_breaking_line(
+    changeset: &changeset::Changeset,
+    repo_info: &Option<context::RepoInfo>,
+    commit_hash: &Option<String>,
+) -> Option<String> {
+    let breaking = changeset.breaking.as_ref()?;
+    let mut line = String::from("- ");
+    line.push_str(&format_commit_link(repo_info, commit_hash));
+    line.push_str(breaking);
+    Some(line)
+}
+
+/// Index the introducing commits of changesets with a single history walk,
+/// bounded by the last release tag.
+pub fn build_commit_index(
+    ctx: &context::Context,
+    repo: &git2::Repository,
+    changesets: &[changeset::Changeset],
+) -> Result<utils::CommitIndex, ResolveError> {
+    let repo_root = ctx.repo_root.as_ref().unwrap();
+    let changeset_root = ctx.changeset_root.as_ref().unwrap();
+    let changeset_dir =
+        pathdiff::diff_paths(changeset_root, repo_root).ok_or(ResolveError::InvalidChangeset {
+            path: changeset_root.to_path_buf(),
+            reason: "Changeset root is not under repo root".to_string(),
+        })?;
+
+    let paths = changesets
+        .iter()
+        .filter_map(|c| c.path.as_ref())
+        .map(|path| {
+            pathdiff::diff_paths(path, repo_root).ok_or(ResolveError::InvalidChangeset {
+                path: path.to_path_buf(),
+                reason: "Changeset path is not under repo root".to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let since = utils::find_last_release_tag(
+        repo,
+        ctx.get_packages()
+            .into_iter()
+            .map(|(name, _)| name.as_str()),
+    );
+    log::debug!("Last release tag commit: {since:?}");
+
+    let git_error = |e: git2::Error| ResolveError::GitError {
+        message: e.message().to_string(),
+    };
+    let mut index =
+        utils::CommitIndex::build(repo, &changeset_dir, &paths, since).map_err(git_error)?;
+    for oid in changesets.iter().filter_map(|c| c.commit.as_ref()) {
+        let commit = git2::Oid::from_str(oid)
+            .and_then(|oid| repo.find_commit(oid))
+            .map_err(git_error)?;
+        index.insert_commit(utils::CommitInfo {
+            oid: commit.id(),
+            message: commit.message().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+        });
+    }
+    Ok(index)
+}
+
+/// Compare links of a package for the repository of the context, if any.
+pub fn compare_links(ctx: &context::Context, package_name: &str) -> Option<types::CompareLinks> {
+    ctx.repo_info.as_ref().map(|repo_info| types::CompareLinks {
+        repo_url: RepoContext::from(repo_info).url,
+        package: package_name.to_string(),
+    })
+}
+
+/// Create a pull request resolver for the repository of the context, if any.
+pub fn create_pr_resolver(ctx: &context::Context) -> Option<PrResolver> {
+    ctx.repo_info
+        .as_ref()
+        .map(|repo_info| PrResolver::new(&repo_info.owner, &repo_info.repo_name))
+}
+
+/// Find the introducing commit of a changeset in the commit index.
+fn find_commit<'a>(
+    ctx: &context::Context,
+    commit_index: &'a utils::CommitIndex,
+    changeset: &changeset::Changeset,
+) -> Result<Option<&'a utils::CommitInfo>, ResolveError> {
+    if let Some(oid) = &changeset.commit {
+        return Ok(commit_index.get_commit(oid));
+    }
+    let Some(changeset_path) = changeset.path.as_ref() else {
+        return Ok(None);
+    };
+    let rel_path = pathdiff::diff_paths(changeset_path, ctx.repo_root.as_ref().unwrap()).ok_or(
+        ResolveError::InvalidChangeset {
+            path: changeset_path.to_path_buf(),
+            reason: "Changeset path is not under repo root".to_string(),
+        },
+    )?;
+    Ok(commit_index.get(&rel_path))
+}
+
+/// Resolve the pull request of a changeset.
+///
+/// Metadata recorded at creation time survives shallow clones, renames and
+/// squashes, so it is preferred over the introducing commit.
+async fn find_pr(
+    ctx: &context::Context,
+    pr_resolver: Option<&mut PrResolver>,
+    changeset: &changeset::Changeset,
+    commit_info: Option<&utils::CommitInfo>,
+) -> Result<Option<PrInfo>, ResolveError> {
+    if let Some(pr_number) = changeset.pr {
+        let url = ctx.repo_info.as_ref().map(|repo_info| {
+            format!(
+                "https://github.com/{}/{}/pull/{pr_number}",
+                repo_info.owner, repo_info.repo_name
+            )
+        });
+        return Ok(Some(PrInfo {
+            number: pr_number,
+            author: None,
+            url,
+        }));
+    }
+    match (pr_resolver, commit_info) {
+        (Some(pr_resolver), Some(commit_info)) => pr_resolver.get(commit_info).await,
+        _ => Ok(None),
+    }
+}
+
+/// Internal dependencies of a package bumped in the current run.
+fn updated_dependencies(
+    ctx: &context::Context,
+    package_name: &str,
+) -> Result<Vec<(String, String)>, ResolveError> {
+    let (Some(root), Some(package_config)) = (
+        ctx.repo_root.as_ref(),
+        ctx.config
+            .as_ref()
+            .and_then(|c| c.packages.get(package_name)),
+    ) else {
+        return Ok(Vec::new());
+    };
+    let version_bumps = ctx.version_bumps.borrow();
+    if version_bumps.is_empty() {
+        return Ok(Vec::new());
     }
 
-    line
+    let mut dependencies = ctx
+        .create_resolver(package_config.resolver)
+        .dependencies(root, package_config)?
+        .into_iter()
+        .filter(|name| name != package_name)
+        .filter_map(|name| {
+            let version = version_bumps.get(&name)?.to_string();
+            Some((name, version))
+        })
+        .collect::<Vec<_>>();
+    dependencies.sort();
+    dependencies.dedup();
+    Ok(dependencies)
 }
 
 pub async fn generate_changelog(
     ctx: &context::Context,
-    repo: &git2::Repository,
+    commit_index: &utils::CommitIndex,
+    pr_resolver: Option<&mut PrResolver>,
     changesets: &[changeset::Changeset],
     package_name: &str,
     package_version: &str,
 ) -> Result<String, ResolveError> {
-    let mut changes_map = HashMap::new();
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    generate_changelog_at(
+        ctx,
+        commit_index,
+        pr_resolver,
+        changesets,
+        package_name,
+        package_version,
+        &date,
+    )
+    .await
+}
 
-    let tags = ctx
-        .config
-        .as_ref()
-        .map(|c| c.tags.clone())
-        .unwrap_or_default();
+/// Generate the changelog of a release made on `date`, formatted as
+/// `YYYY-MM-DD`.
+pub async fn generate_changelog_at(
+    ctx: &context::Context,
+    commit_index: &utils::CommitIndex,
+    mut pr_resolver: Option<&mut PrResolver>,
+    changesets: &[changeset::Changeset],
+    package_name: &str,
+    package_version: &str,
+    date: &str,
+) -> Result<String, ResolveError> {
+    let mut changes_map: HashMap<Option<&str>, Vec<_>> = HashMap::new();
+    let mut breaking_lines = Vec::new();
+    let mut contributions = Vec::new();
 
+    let config = ctx.config.as_ref();
+    let tags = config.map(|c| &c.tags);
+    let templates = match config {
+        Some(config) => Templates::new(&config.changelog)?,
+        None => Templates::default(),
+    };
     for changeset in changesets {
-        let changeset_path = changeset.path.as_ref().unwrap();
-        let rel_path = pathdiff::diff_paths(changeset_path, ctx.repo_root.as_ref().unwrap())
-            .ok_or(ResolveError::InvalidChangeset {
-                path: changeset_path.to_path_buf(),
-                reason: "Changeset path is not under repo root".to_string(),
-            })?;
-        let commit_info = utils::find_first_commit_for_path(repo, &rel_path);
-        let commit_hash = commit_info.as_ref().map(|c| c.oid.to_string());
-        let pr_info = if let Some(repo_info) = ctx.repo_info.as_ref()
-            && let Some(commit_info) = commit_info.as_ref()
-        {
-            utils::query_pr_for_commit(
-                repo_info.owner.as_str(),
-                repo_info.repo_name.as_str(),
-                commit_info,
-            )
-            .await
-            .map_err(|e| ResolveError::GitHubError {
-                message: format!("Failed to query PR for commit: {:?}", e),
-            })?
+        let Some(package) = changeset.packages.iter().find(|p| p.name == package_name) else {
+            continue;
+        };
+
+        let commit_info = find_commit(ctx, commit_index, changeset)?;
+        let commit_hash = commit_info.map(|c| c.oid.to_string());
+        // Uncommitted changesets are the newest ones
+        let sort_key = (
+            commit_info.map(|c| c.time).unwrap_or(i64::MAX),
+            changeset.name.clone(),
+        );
+
+        breaking_lines.extend(
+            format_breaking_line(changeset, &ctx.repo_info, &commit_hash)
+                .map(|line| (sort_key.clone(), line)),
+        );
+
+        let tag_config = package
+            .tag
+            .as_deref()
+            .and_then(|t| tags.and_then(|tags| tags.get_key_value(t)));
+        if tag_config.is_some_and(|(_, tag_config)| tag_config.is_hidden()) {
+            continue;
+        }
+
+        let pr_info = find_pr(ctx, pr_resolver.as_deref_mut(), changeset, commit_info).await?;
+        let line_ctx = LineContext {
+            package: package_name,
+            version: package_version,
+            date,
+            tag: tag_config.map(|(tag, tag_config)| TagContext {
+                name: tag,
+                title: tag_config.title(),
+            }),
+            ..LineContext::new(changeset, &ctx.repo_info, &pr_info, &commit_hash)
+        };
+        let line = templates.render_line(&line_ctx)?;
+        // Without pull request information, the commit author is the contributor
+        let authors = if line_ctx.authors.is_empty() && pr_info.is_none() {
+            commit_info
+                .and_then(|c| commit_author_login(ctx.git_repo.as_ref()?, c.oid))
+                .into_iter()
+                .collect()
         } else {
-            None
+            line_ctx.authors.clone()
         };
+        // Commits only identify the contributor when they are the sole author
+        let sole_author = authors.len() == 1;
+        contributions.extend(authors.iter().map(|login| {
+            (
+                sort_key.clone(),
+                Contribution {
+                    login: login.clone(),
+                    commit: commit_info.filter(|_| sole_author).map(|c| c.oid),
+                    pr: pr_info.clone(),
+                },
+            )
+        }));
+        changes_map
+            .entry(tag_config.map(|(tag, _)| tag.as_str()))
+            .or_default()
+            .push((sort_key, line));
+    }
+
+    let default_changelog_config = ChangelogConfig::default();
+    let changelog_config = config.map_or(&default_changelog_config, |c| &c.changelog);
 
-        let package = changeset.packages.iter().find(|p| p.name == package_name);
-        if let Some(package) = package {
-            let tag = package
-                .tag
-                .as_ref()
-                .and_then(|t| tags.get(t).map(|s| s.as_str()))
-                .unwrap_or("Changes");
-            changes_map
-                .entry(tag)
-                .or_insert_with(Vec::new)
-                .push(format_line(
-                    changeset,
-                    &ctx.repo_info,
-                    &pr_info,
-                    &commit_hash,
-                ));
+    // Tag sections follow the config order, untagged changes go first or last
+    let mut sections = tags
+        .into_iter()
+        .flatten()
+        .map(|(tag, tag_config)| (Some(tag.as_str()), tag_config.title()))
+        .collect::<Vec<_>>();
+    let untagged = (None, changelog_config.untagged_title.as_str());
+    match changelog_config.untagged_position {
+        SectionPosition::First => sections.insert(0, untagged),
+        SectionPosition::Last => sections.push(untagged),
+    }
+
+    let mut sections = sections
+        .into_iter()
+        .filter_map(|(tag, title)| Some((tag, title, changes_map.remove(&tag)?)))
+        .collect::<Vec<_>>();
+    if !changelog_config.hide_dependencies {
+        let dependency_lines = updated_dependencies(ctx, package_name)?
+            .into_iter()
+            .map(|(name, version)| ((0, name.clone()), format!("- {name}@{version}")))
+            .collect::<Vec<_>>();
+        if !dependency_lines.is_empty() {
+            sections.push((
+                None,
+                changelog_config.dependencies_title.as_str(),
+                dependency_lines,
+            ));
         }
     }
+    let list_contributors = config
+        .and_then(|c| c.packages.get(package_name))
+        .and_then(|p| p.contributors)
+        .unwrap_or(changelog_config.contributors);
+    if list_contributors && !contributions.is_empty() {
+        contributions.sort_by(|a, b| a.0.cmp(&b.0));
+        let contributions = contributions
+            .into_iter()
+            .map(|(_, contribution)| contribution)
+            .collect::<Vec<_>>();
+        let contributors =
+            find_contributors(ctx.git_repo.as_ref(), pr_resolver, &contributions).await?;
+        // Keep the order of contributions, lines are sorted by key
+        let line = |idx: usize, text: String| ((idx as i64, String::new()), text);
 
-    let header = format!("## v{package_version}\n\n");
-    let body = changes_map
-        .iter()
-        .map(|(tag, lines)| format!("### {tag}\n\n{}", lines.join("\n")))
-        .collect::<Vec<_>>()
+        sections.push((
+            None,
+            changelog_config.contributors_title.as_str(),
+            contributors
+                .iter()
+                .enumerate()
+                .map(|(idx, c)| line(idx, format!("- @{}", c.login)))
+                .collect(),
+        ));
+        let new_contributors = contributors
+            .iter()
+            .filter(|c| c.is_new)
+            .enumerate()
+            .map(|(idx, c)| {
+                let text = match &c.first_pr {
+                    Some(PrInfo {
+                        number,
+                        url: Some(url),
+                        ..
+                    }) => format!(
+                        "- @{} made their first contribution in [#{number}]({url})",
+                        c.login
+                    ),
+                    Some(pr) => format!(
+                        "- @{} made their first contribution in #{}",
+                        c.login, pr.number
+                    ),
+                    None => format!("- @{} made their first contribution", c.login),
+                };
+                line(idx, text)
+            })
+            .collect::<Vec<_>>();
+        if !new_contributors.is_empty() {
+            sections.push((
+                None,
+                changelog_config.new_contributors_title.as_str(),
+                new_contributors,
+            ));
+        }
+    }
+    if !breaking_lines.is_empty() {
+        sections.insert(0, (None, "Breaking Changes", breaking_lines));
+    }
+
+    let header = templates.render_header(&HeaderContext {
+        package: package_name,
+        version: package_version,
+        date,
+        repo: ctx.repo_info.as_ref().map(RepoContext::from),
+    })?;
+    let body = sections
+        .into_iter()
+        .map(|(tag, title, mut lines)| {
+            lines.sort_by(|a, b| a.0.cmp(&b.0));
+            let section = templates.render_section(&SectionContext {
+                package: package_name,
+                version: package_version,
+                title,
+                tag,
+            })?;
+            let lines = lines.into_iter().map(|(_, line)| line).collect::<Vec<_>>();
+            Ok(format!("{section}\n\n{}", lines.join("\n")))
+        })
+        .collect::<Result<Vec<_>, ResolveError>>()?
         .join("\n\n");
 
-    Ok(header + &body)
+    Ok(format!("{header}\n\n{body}"))
+}
+
+/// Demote the markdown headings of a package changelog by one level,
+/// dropping its release header.
+fn demote_headings(changelog: &str) -> String {
+    let mut in_code_block = false;
+    changelog
+        .lines()
+        .skip_while(|line| line.trim().is_empty())
+        .skip(1)
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            if !in_code_block && line.starts_with('#') {
+                format!("#{line}")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Combine the changelogs of packages released in one run into a single
+/// dated entry, with a subsection per package.
+///
+/// The heading lists the released versions, so that entries of releases on
+/// the same day are told apart, e.g. `## 2026-10-19 (a v1.1.0, b v0.3.1)`.
+///
+/// `releases` holds `(package, version, changelog)` in release order.
+pub fn generate_root_changelog(releases: &[(&str, &str, &str)], date: &str) -> String {
+    let packages = releases
+        .iter()
+        .map(|(package, version, changelog)| {
+            let body = demote_headings(changelog);
+            if body.is_empty() {
+                format!("### {package} v{version}")
+            } else {
+                format!("### {package} v{version}\n\n{body}")
+            }
+        })
+        .collect::<Vec<_>>();
+    let versions = releases
+        .iter()
+        .map(|(package, version, _)| format!("{package} v{version}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("## {date} ({versions})\n\n{}", packages.join("\n\n"))
 }
 
 pub async fn read_latest_changelog<P: AsRef<Path>>(
     path: P,
 ) -> Result<types::Changelog, ResolveError> {
-    let content = std::fs::read_to_string(path.as_ref())?;
+    let changelog = parser::read_changelog(path.as_ref())?;
+    let release = changelog
+        .releases
+        .into_iter()
+        .next()
+        .ok_or(ResolveError::InvalidChangelog {
+            path: path.as_ref().to_path_buf(),
+            reason: "No version header found".to_string(),
+        })?;
 
-    let mut lines = content.lines();
+    let version = if semver::Version::parse(&release.version).is_ok() {
+        format!("v{}", release.version)
+    } else {
+        release.version
+    };
 
-    if lines.next().map(|l| l.trim()) != Some("# Changelog") {
-        return Err(ResolveError::InvalidChangelog {
-            path: path.as_ref().to_path_buf(),
-            reason: "Invalid changelog: missing `# Changelog` header".to_string(),
-        });
+    Ok(types::Changelog {
+        version,
+        body: release.body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, block_on, commit_as, temp_dir, temp_repo};
+
+    /// Changeset of package `a`, committed at `time` unless it is `None`.
+    fn changeset_at(
+        commit_index: &mut utils::CommitIndex,
+        summary: &str,
+        tag: Option<&str>,
+        time: Option<i64>,
+    ) -> changeset::Changeset {
+        let mut changeset = changeset::Changeset::new(summary.to_string(), Path::new(".changes"));
+        changeset.add_package(
+            "a".to_string(),
+            changeset::BumpLevel::Patch,
+            tag.map(String::from),
+        );
+        changeset.summary(summary.to_string());
+        if let Some(time) = time {
+            let oid = git2::Oid::from_bytes(&[time as u8; 20]).unwrap();
+            commit_index.insert_commit(utils::CommitInfo {
+                oid,
+                message: summary.to_string(),
+                time,
+            });
+            changeset.commit = Some(oid.to_string());
+        }
+        changeset
     }
 
-    let mut version: Option<String> = None;
-    let mut body = String::new();
-    let mut in_latest = false;
+    #[test]
+    fn orders_sections_by_config_and_lines_by_commit_time() {
+        let config = test_utils::config(serde_json::json!({
+            "tags": {
+                "fix": "Bug Fixes",
+                "feat": "New Features",
+                "chore": { "title": "Chores", "hidden": true },
+            },
+            "changelog": { "untagged-title": "Other", "untagged-position": "first" },
+        }));
+        let ctx = context::Context {
+            config: Some(config),
+            ..Default::default()
+        };
+        let mut index = utils::CommitIndex::default();
+        let changesets = [
+            changeset_at(&mut index, "Uncommitted feature", Some("feat"), None),
+            changeset_at(&mut index, "Later feature", Some("feat"), Some(20)),
+            changeset_at(&mut index, "Earlier feature", Some("feat"), Some(10)),
+            changeset_at(&mut index, "Hidden chore", Some("chore"), Some(15)),
+            changeset_at(&mut index, "Bug fix", Some("fix"), Some(30)),
+            changeset_at(&mut index, "Untagged change", None, Some(5)),
+        ];
 
-    for line in content.lines().skip(1) {
-        let trimmed = line.trim();
+        let changelog = block_on(generate_changelog_at(
+            &ctx,
+            &index,
+            None,
+            &changesets,
+            "a",
+            "1.0.1",
+            "2026-10-19",
+        ))
+        .unwrap();
+        assert_eq!(
+            changelog,
+            "## v1.0.1\n\n\
+            ### Other\n\n- Untagged change\n\n\
+            ### Bug Fixes\n\n- Bug fix\n\n\
+            ### New Features\n\n- Earlier feature\n- Later feature\n- Uncommitted feature"
+        );
+    }
 
-        if version.is_none() {
-            if let Some(rest) = trimmed.strip_prefix("## ") {
-                version = Some(rest.to_string());
-                in_latest = true;
+    #[test]
+    fn lists_bumped_internal_dependencies() {
+        let root = temp_dir("deps");
+        std::fs::create_dir_all(root.join("app")).unwrap();
+        std::fs::create_dir_all(root.join("core")).unwrap();
+        std::fs::write(
+            root.join("app/pyproject.toml"),
+            "[project]\nname = \"app\"\nversion = \"1.0.0\"\ndependencies = [\"lib>=1.0\", \"requests\", \"core\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("core/vcpkg.json"),
+            r#"{ "name": "core", "version": "1.0.0", "dependencies": ["fmt", { "name": "lib" }] }"#,
+        )
+        .unwrap();
 
-                body.push_str(line);
-                body.push('\n');
-                continue;
-            }
-        } else if in_latest && trimmed.starts_with("## ") {
-            break;
-        } else if in_latest {
-            body.push_str(line);
-            body.push('\n');
-        }
+        let config = test_utils::config(serde_json::json!({
+            "packages": {
+                "app": { "path": "app", "resolver": "python" },
+                "core": { "path": "core", "resolver": "cpp" },
+            },
+        }));
+        let ctx = context::Context {
+            config: Some(config),
+            repo_root: Some(root.clone()),
+            ..Default::default()
+        };
+        ctx.version_bumps.borrow_mut().extend([
+            ("app".to_string(), semver::Version::new(1, 1, 0)),
+            ("lib".to_string(), semver::Version::new(0, 2, 0)),
+            ("other".to_string(), semver::Version::new(3, 0, 0)),
+        ]);
+
+        assert_eq!(
+            updated_dependencies(&ctx, "app").unwrap(),
+            [("lib".to_string(), "0.2.0".to_string())]
+        );
+        assert_eq!(
+            updated_dependencies(&ctx, "core").unwrap(),
+            [("lib".to_string(), "0.2.0".to_string())]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
     }
 
-    let version = version.ok_or(ResolveError::InvalidChangelog {
-        path: path.as_ref().to_path_buf(),
-        reason: "No version header found".to_string(),
-    })?;
+    #[test]
+    fn lists_updated_dependencies_of_dependency_only_releases() {
+        let root = temp_dir("deps-only");
+        std::fs::create_dir_all(root.join("b")).unwrap();
+        std::fs::write(
+            root.join("b/Cargo.toml"),
+            "[package]\nname = \"b\"\nversion = \"0.3.0\"\n\n[dependencies]\na = { version = \"1.0.0\", path = \"../a\" }\n",
+        )
+        .unwrap();
 
-    Ok(types::Changelog {
-        version,
-        body: body.trim().to_string(),
-    })
+        let config = test_utils::config(serde_json::json!({
+            "packages": {
+                "a": { "path": "a", "resolver": "rust" },
+                "b": { "path": "b", "resolver": "rust" },
+            },
+        }));
+        let ctx = context::Context {
+            config: Some(config),
+            repo_root: Some(root.clone()),
+            ..Default::default()
+        };
+        ctx.version_bumps
+            .borrow_mut()
+            .insert("a".to_string(), semver::Version::new(1, 1, 0));
+        let mut changeset = changeset::Changeset::new("feat".to_string(), Path::new(".changes"));
+        changeset.add_package("a".to_string(), changeset::BumpLevel::Minor, None);
+        changeset.summary("Add a feature".to_string());
+
+        let changelog = block_on(generate_changelog_at(
+            &ctx,
+            &utils::CommitIndex::default(),
+            None,
+            &[changeset],
+            "b",
+            "0.3.1",
+            "2026-10-19",
+        ))
+        .unwrap();
+        assert_eq!(
+            changelog,
+            "## v0.3.1\n\n### Updated Dependencies\n\n- a@1.1.0"
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn lists_commit_authors_as_contributors_without_pull_requests() {
+        let (root, repo) = temp_repo("commit-authors");
+        let mut commit_index = utils::CommitIndex::default();
+        let mut commit = |name: &str, email: &str, message: &str| {
+            let oid = commit_as(&repo, name, email, &[], message);
+            commit_index.insert_commit(utils::CommitInfo {
+                oid,
+                message: message.to_string(),
+                time: repo.find_commit(oid).unwrap().time().seconds(),
+            });
+            oid
+        };
+        commit("alice", "alice@example.com", "init");
+        let alice = commit("alice", "alice@example.com", "fix: a bug");
+        let bob = commit("Bob", "42+bob@users.noreply.github.com", "feat: a feature");
+
+        let config = test_utils::config(serde_json::json!({
+            "changelog": {
+                "contributors": true,
+                "contributors-title": "Thanks",
+                "new-contributors-title": "Welcome",
+            },
+        }));
+        let ctx = context::Context {
+            config: Some(config),
+            git_repo: Some(git2::Repository::open(&root).unwrap()),
+            ..Default::default()
+        };
+        // Commits of the same second are ordered by changeset name
+        let changesets = [(alice, "Fix a bug"), (bob, "Add a feature")].map(|(oid, summary)| {
+            let mut changeset =
+                changeset::Changeset::new(summary.to_string(), Path::new(".changes"));
+            changeset.add_package("a".to_string(), changeset::BumpLevel::Patch, None);
+            changeset.summary(summary.to_string());
+            changeset.commit = Some(oid.to_string());
+            changeset
+        });
+
+        let changelog = block_on(generate_changelog_at(
+            &ctx,
+            &commit_index,
+            None,
+            &changesets,
+            "a",
+            "1.0.1",
+            "2026-10-19",
+        ))
+        .unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            changelog,
+            "## v1.0.1\n\n\
+            ### Changes\n\n- Add a feature\n- Fix a bug\n\n\
+            ### Thanks\n\n- @bob\n- @alice\n\n\
+            ### Welcome\n\n- @bob made their first contribution"
+        );
+    }
+
+    #[test]
+    fn keeps_commit_link_of_recorded_pull_request() {
+        let ctx = context::Context {
+            repo_info: Some(context::RepoInfo {
+                owner: "owner".to_string(),
+                repo_name: "repo".to_string(),
+            }),
+            ..Default::default()
+        };
+        let oid = git2::Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        let mut commit_index = utils::CommitIndex::default();
+        commit_index.insert_commit(utils::CommitInfo {
+            oid,
+            message: "fix: a bug".to_string(),
+            time: 0,
+        });
+        let mut changeset = changeset::Changeset::new("fix".to_string(), Path::new(".changes"));
+        changeset.add_package("a".to_string(), changeset::BumpLevel::Patch, None);
+        changeset.summary("Fix a bug".to_string());
+        changeset.pr = Some(12);
+        changeset.commit = Some(oid.to_string());
+
+        let changelog = block_on(generate_changelog_at(
+            &ctx,
+            &commit_index,
+            None,
+            &[changeset],
+            "a",
+            "1.0.1",
+            "2026-10-19",
+        ))
+        .unwrap();
+        assert_eq!(
+            changelog,
+            "## v1.0.1\n\n### Changes\n\n- [`0123456`](https://github.com/owner/repo/commit/0123456789abcdef0123456789abcdef01234567): Fix a bug ([#12](https://github.com/owner/repo/pull/12))"
+        );
+    }
+
+    #[test]
+    fn aggregates_package_changelogs() {
+        let root = generate_root_changelog(
+            &[
+                ("a", "1.1.0", "## v1.1.0\n\n### New Features\n\n- Feat"),
+                (
+                    "b",
+                    "0.3.1",
+                    "## v0.3.1\n\n### Bug Fixes\n\n- Fix\n\n```sh\n# not a heading\n```",
+                ),
+            ],
+            "2026-10-19",
+        );
+
+        assert_eq!(
+            root,
+            "## 2026-10-19 (a v1.1.0, b v0.3.1)\n\n### a v1.1.0\n\n#### New Features\n\n- Feat\n\n### b v0.3.1\n\n#### Bug Fixes\n\n- Fix\n\n```sh\n# not a heading\n```"
+        );
+    }
 }
@@ -0,0 +1,170 @@
+use std::{path::Path, sync::LazyLock};
+
+use regex::Regex;
+use semifold_resolver::error::ResolveError;
+
+use crate::{
+    types::{ParsedChangelog, Release, Section},
+    utils,
+};
+
+static DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(\d{4}-\d{2}-\d{2})\b").unwrap());
+
+/// Parse a CHANGELOG.md file into typed releases.
+pub fn read_changelog<P: AsRef<Path>>(path: P) -> Result<ParsedChangelog, ResolveError> {
+    let content = std::fs::read_to_string(path.as_ref())?;
+    parse_changelog(&content).map_err(|reason| ResolveError::InvalidChangelog {
+        path: path.as_ref().to_path_buf(),
+        reason,
+    })
+}
+
+/// Parse the content of a CHANGELOG.md file into typed releases.
+///
+/// Both the default and the Keep a Changelog formats are supported.
+pub fn parse_changelog(content: &str) -> Result<ParsedChangelog, String> {
+    let (lines, links) = utils::split_link_definitions(content);
+    let mut lines = lines.into_iter();
+
+    if lines.next().map(|l| l.trim()) != Some("# Changelog") {
+        return Err("Invalid changelog: missing `# Changelog` header".to_string());
+    }
+
+    let mut changelog = ParsedChangelog {
+        links,
+        ..Default::default()
+    };
+    let mut current: Option<(bool, Release)> = None;
+
+    for line in lines {
+        let trimmed = line.trim();
+
+        if let Some(rest) = trimmed.strip_prefix("## ") {
+            flush(current.take(), &mut changelog);
+            let version = utils::parse_release_heading(trimmed);
+            let is_unreleased = version.is_none()
+                && rest
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .eq_ignore_ascii_case("unreleased");
+            current = Some((
+                is_unreleased,
+                Release {
+                    version: version.unwrap_or_else(|| rest.trim().to_string()),
+                    date: parse_date(rest),
+                    sections: Vec::new(),
+                    body: format!("{line}\n"),
+                },
+            ));
+            continue;
+        }
+
+        // Preamble before the first release
+        let Some((_, release)) = current.as_mut() else {
+            continue;
+        };
+        release.body.push_str(line);
+        release.body.push('\n');
+
+        if let Some(title) = trimmed.strip_prefix("### ") {
+            release.sections.push(Section {
+                title: Some(title.trim().to_string()),
+                entries: Vec::new(),
+            });
+        } else if let Some(entry) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+            .filter(|_| !line.starts_with([' ', '\t']))
+        {
+            if release.sections.is_empty() {
+                release.sections.push(Section {
+                    title: None,
+                    entries: Vec::new(),
+                });
+            }
+            let section = release.sections.last_mut().unwrap();
+            section.entries.push(entry.to_string());
+        } else if !trimmed.is_empty()
+            && let Some(entry) = release
+                .sections
+                .last_mut()
+                .and_then(|section| section.entries.last_mut())
+        {
+            // Continuation of a multi-line entry
+            entry.push('\n');
+            entry.push_str(trimmed);
+        }
+    }
+    flush(current.take(), &mut changelog);
+
+    Ok(changelog)
+}
+
+/// Finish the current release.
+fn flush(current: Option<(bool, Release)>, changelog: &mut ParsedChangelog) {
+    if let Some((is_unreleased, mut release)) = current {
+        release.body = release.body.trim().to_string();
+        if is_unreleased {
+            changelog.unreleased = Some(release);
+        } else {
+            changelog.releases.push(release);
+        }
+    }
+}
+
+fn parse_date(heading: &str) -> Option<String> {
+    DATE.captures(heading).map(|caps| caps[1].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_default_format() {
+        let changelog = parse_changelog(
+            "# Changelog\n\n## v1.1.0\n\n### New Features\n\n- Feat one\n- Feat two\n  continued\n\n### Bug Fixes\n\n- Fix\n\n## v1.0.0\n\n- Initial\n",
+        )
+        .unwrap();
+
+        assert!(changelog.unreleased.is_none());
+        assert_eq!(changelog.releases.len(), 2);
+        let latest = &changelog.releases[0];
+        assert_eq!(latest.version, "1.1.0");
+        assert_eq!(latest.date, None);
+        assert_eq!(latest.sections.len(), 2);
+        assert_eq!(latest.sections[0].title.as_deref(), Some("New Features"));
+        assert_eq!(
+            latest.sections[0].entries,
+            ["Feat one", "Feat two\ncontinued"]
+        );
+        assert!(latest.body.starts_with("## v1.1.0"));
+        assert!(latest.body.ends_with("- Fix"));
+        assert_eq!(changelog.releases[1].sections[0].title, None);
+    }
+
+    #[test]
+    fn parses_keep_a_changelog_format() {
+        let changelog = parse_changelog(
+            "# Changelog\n\nPreamble.\n\n## [Unreleased]\n\n### Added\n\n- Pending\n\n## [1.2.0] - 2026-10-18\n\n### Fixed\n\n- Fix\n\n[unreleased]: https://example.com/compare/a-v1.2.0...HEAD\n[1.2.0]: https://example.com/releases/tag/a-v1.2.0\n",
+        )
+        .unwrap();
+
+        let unreleased = changelog.unreleased.unwrap();
+        assert_eq!(unreleased.sections[0].entries, ["Pending"]);
+        assert_eq!(changelog.releases.len(), 1);
+        assert_eq!(changelog.releases[0].version, "1.2.0");
+        assert_eq!(changelog.releases[0].date.as_deref(), Some("2026-10-18"));
+        assert_eq!(
+            changelog.releases[0].body,
+            "## [1.2.0] - 2026-10-18\n\n### Fixed\n\n- Fix"
+        );
+        assert_eq!(changelog.links.len(), 2);
+        assert_eq!(changelog.links[1].0, "1.2.0");
+    }
+
+    #[test]
+    fn rejects_missing_header() {
+        assert!(parse_changelog("## v1.0.0\n").is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use minijinja::Environment;
+use semifold_resolver::{
+    changeset::Changeset,
+    config::{ChangelogConfig, ChangelogFormat},
+    context::RepoInfo,
+    error::ResolveError,
+};
+use serde::Serialize;
+
+use crate::utils::PrInfo;
+
+/// Default template of the release header.
+pub const DEFAULT_HEADER_TEMPLATE: &str = "## v{{ version }}";
+
+/// Default template of the release header in the Keep a Changelog format.
+pub const KEEP_A_CHANGELOG_HEADER_TEMPLATE: &str = "## [{{ version }}] - {{ date }}";
+
+/// Default template of the section headers.
+pub const DEFAULT_SECTION_TEMPLATE: &str = "### {{ title }}";
+
+/// Default template of the change lines.
+pub const DEFAULT_LINE_TEMPLATE: &str = "\
+- {% if commit and commit.url %}[`{{ commit.short_sha }}`]({{ commit.url }}): {% endif %}{{ changeset.summary }}
+{%- if pr %} ({% if pr.url %}[#{{ pr.number }}]({{ pr.url }}){% else %}#{{ pr.number }}{% endif %}
+{%- if authors %} by {% for author in authors %}@{{ author }}{% if not loop.last %}, {% endif %}{% endfor %}{% endif %})
+{%- elif authors %} (by {% for author in authors %}@{{ author }}{% if not loop.last %}, {% endif %}{% endfor %})
+{%- endif %}
+{%- if issues %} Fixes {% for issue in issues %}{% if issue.url %}[#{{ issue.number }}]({{ issue.url }}){% else %}#{{ issue.number }}{% endif %}{% if not loop.last %}, {% endif %}{% endfor %}
+{%- endif %}";
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RepoContext {
+    pub owner: String,
+    pub name: String,
+    pub url: String,
+}
+
+impl From<&RepoInfo> for RepoContext {
+    fn from(repo_info: &RepoInfo) -> Self {
+        Self {
+            owner: repo_info.owner.clone(),
+            name: repo_info.repo_name.clone(),
+            url: format!(
+                "https://github.com/{}/{}",
+                repo_info.owner, repo_info.repo_name
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct HeaderContext<'a> {
+    pub package: &'a str,
+    pub version: &'a str,
+    /// Release date, formatted as `YYYY-MM-DD`.
+    pub date: &'a str,
+    pub repo: Option<RepoContext>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SectionContext<'a> {
+    pub package: &'a str,
+    pub version: &'a str,
+    pub title: &'a str,
+    /// Tag of the section, `None` for untagged changes.
+    pub tag: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TagContext<'a> {
+    pub name: &'a str,
+    pub title: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CommitContext {
+    pub sha: String,
+    pub short_sha: String,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PrContext {
+    pub number: u64,
+    pub url: Option<String>,
+    pub author: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct IssueContext {
+    pub number: u64,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LineContext<'a> {
+    pub package: &'a str,
+    pub version: &'a str,
+    pub date: &'a str,
+    pub changeset: &'a Changeset,
+    pub tag: Option<TagContext<'a>>,
+    pub commit: Option<CommitContext>,
+    pub pr: Option<PrContext>,
+    /// Authors recorded in the changeset, or the pull request author.
+    pub authors: Vec<String>,
+    pub issues: Vec<IssueContext>,
+    pub repo: Option<RepoContext>,
+}
+
+impl<'a> LineContext<'a> {
+    pub fn new(
+        changeset: &'a Changeset,
+        repo_info: &Option<RepoInfo>,
+        pr_info: &Option<PrInfo>,
+        commit_hash: &Option<String>,
+    ) -> Self {
+        let repo = repo_info.as_ref().map(RepoContext::from);
+        let commit = commit_hash.as_ref().map(|sha| CommitContext {
+            sha: sha.clone(),
+            short_sha: sha.chars().take(7).collect(),
+            url: repo
+                .as_ref()
+                .map(|repo| format!("{}/commit/{sha}", repo.url)),
+        });
+        // Authors recorded in the changeset take precedence over the PR author
+        let authors = if changeset.authors.is_empty() {
+            pr_info
+                .as_ref()
+                .and_then(|p| p.author.clone())
+                .into_iter()
+                .collect()
+        } else {
+            changeset.authors.clone()
+        };
+        let issues = changeset
+            .issues
+            .iter()
+            .map(|&number| IssueContext {
+                number,
+                url: repo
+                    .as_ref()
+                    .map(|repo| format!("{}/issues/{number}", repo.url)),
+            })
+            .collect();
+
+        Self {
+            package: "",
+            version: "",
+            date: "",
+            changeset,
+            tag: None,
+            commit,
+            pr: pr_info.as_ref().map(|pr_info| PrContext {
+                number: pr_info.number,
+                url: pr_info.url.clone(),
+                author: pr_info.author.clone(),
+            }),
+            authors,
+            issues,
+            repo,
+        }
+    }
+}
+
+/// Compiled changelog templates.
+pub struct Templates<'a> {
+    env: Environment<'a>,
+}
+
+impl<'a> Templates<'a> {
+    /// Compile the templates of the config, falling back to the defaults.
+    pub fn new(config: &'a ChangelogConfig) -> Result<Self, ResolveError> {
+        Self::from_sources([
+            (
+                "header",
+                config.header.as_deref().unwrap_or(match config.format {
+                    ChangelogFormat::Default => DEFAULT_HEADER_TEMPLATE,
+                    ChangelogFormat::KeepAChangelog => KEEP_A_CHANGELOG_HEADER_TEMPLATE,
+                }),
+            ),
+            (
+                "section",
+                config
+                    .section
+                    .as_deref()
+                    .unwrap_or(DEFAULT_SECTION_TEMPLATE),
+            ),
+            (
+                "line",
+                config.line.as_deref().unwrap_or(DEFAULT_LINE_TEMPLATE),
+            ),
+        ])
+    }
+
+    fn from_sources(sources: [(&'static str, &'a str); 3]) -> Result<Self, ResolveError> {
+        let mut env = Environment::new();
+        for (name, source) in sources {
+            env.add_template(name, source)
+                .map_err(|e| ResolveError::TemplateError {
+                    name: name.to_string(),
+                    reason: e.to_string(),
+                })?;
+        }
+        Ok(Self { env })
+    }
+
+    fn render<S: Serialize>(&self, name: &str, ctx: S) -> Result<String, ResolveError> {
+        self.env
+            .get_template(name)
+            .and_then(|template| template.render(ctx))
+            .map_err(|e| ResolveError::TemplateError {
+                name: name.to_string(),
+                reason: e.to_string(),
+            })
+    }
+
+    pub fn render_header(&self, ctx: &HeaderContext) -> Result<String, ResolveError> {
+        self.render("header", ctx)
+    }
+
+    pub fn render_section(&self, ctx: &SectionContext) -> Result<String, ResolveError> {
+        self.render("section", ctx)
+    }
+
+    pub fn render_line(&self, ctx: &LineContext) -> Result<String, ResolveError> {
+        self.render("line", ctx)
+    }
+}
+
+impl Default for Templates<'static> {
+    fn default() -> Self {
+        Self::from_sources([
+            ("header", DEFAULT_HEADER_TEMPLATE),
+            ("section", DEFAULT_SECTION_TEMPLATE),
+            ("line", DEFAULT_LINE_TEMPLATE),
+        ])
+        .expect("default templates are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// Line format of the changelog before templates were configurable.
+    fn legacy_line(
+        changeset: &Changeset,
+        repo_info: &Option<RepoInfo>,
+        pr_info: &Option<PrInfo>,
+        commit_hash: &Option<String>,
+    ) -> String {
+        let mut line = String::from("- ");
+        if let Some(repo_info) = repo_info.as_ref()
+            && let Some(commit_hash) = commit_hash
+        {
+            line.push_str(&format!(
+                "[`{}`](https://github.com/{}/{}/commit/{}): ",
+                &commit_hash[..7],
+                repo_info.owner,
+                repo_info.repo_name,
+                commit_hash
+            ));
+        }
+        line.push_str(&changeset.summary);
+
+        let authors = if changeset.authors.is_empty() {
+            pr_info
+                .as_ref()
+                .and_then(|p| p.author.clone())
+                .into_iter()
+                .collect()
+        } else {
+            changeset.authors.clone()
+        };
+        let authors = authors
+            .iter()
+            .map(|a| format!("@{a}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Some(pr_info) = pr_info.as_ref() {
+            if let Some(url) = pr_info.url.as_ref() {
+                line.push_str(&format!(" ([#{}]({url})", pr_info.number));
+            } else {
+                line.push_str(&format!(" (#{}", pr_info.number));
+            }
+            if !authors.is_empty() {
+                line.push_str(&format!(" by {authors}"));
+            }
+            line.push(')');
+        } else if !authors.is_empty() {
+            line.push_str(&format!(" (by {authors})"));
+        }
+
+        if !changeset.issues.is_empty() {
+            let issues = changeset
+                .issues
+                .iter()
+                .map(|issue| match repo_info {
+                    Some(repo_info) => format!(
+                        "[#{issue}](https://github.com/{}/{}/issues/{issue})",
+                        repo_info.owner, repo_info.repo_name
+                    ),
+                    None => format!("#{issue}"),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            line.push_str(&format!(" Fixes {issues}"));
+        }
+        line
+    }
+
+    #[test]
+    fn default_line_template_matches_legacy_format() {
+        let templates = Templates::default();
+        let repo = Some(RepoInfo {
+            owner: "o".to_string(),
+            repo_name: "r".to_string(),
+        });
+        let sha = Some("0123456789abcdef0123456789abcdef01234567".to_string());
+        let pr = |url: Option<&str>, author: Option<&str>| {
+            Some(PrInfo {
+                number: 42,
+                url: url.map(String::from),
+                author: author.map(String::from),
+            })
+        };
+
+        let mut plain = Changeset::new("plain".to_string(), Path::new(".changes"));
+        plain.summary("Fix a bug".to_string());
+        let mut annotated = plain.clone();
+        annotated.authors = vec!["alice".to_string(), "bob".to_string()];
+        annotated.issues = vec![7, 8];
+
+        let cases = [
+            (&plain, &repo, None, &sha),
+            (&plain, &None, None, &sha),
+            (
+                &plain,
+                &repo,
+                pr(Some("https://github.com/o/r/pull/42"), Some("carol")),
+                &sha,
+            ),
+            (&plain, &repo, pr(None, None), &None),
+            (&annotated, &repo, None, &sha),
+            (&annotated, &None, pr(None, Some("carol")), &None),
+        ];
+        for (changeset, repo_info, pr_info, commit_hash) in cases {
+            let ctx = LineContext::new(changeset, repo_info, &pr_info, commit_hash);
+            assert_eq!(
+                templates.render_line(&ctx).unwrap(),
+                legacy_line(changeset, repo_info, &pr_info, commit_hash)
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Fixtures shared by the tests of the crate.
+
+use std::path::{Path, PathBuf};
+
+use git2::{Oid, Repository};
+use semifold_resolver::config::Config;
+
+/// Config with the required fields defaulted, overridden by `fields`.
+pub(crate) fn config(fields: serde_json::Value) -> Config {
+    let mut config = serde_json::json!({
+        "branches": { "base": "main", "release": "release" },
+        "packages": {},
+        "resolver": {},
+        "tags": {},
+    });
+    config
+        .as_object_mut()
+        .unwrap()
+        .extend(fields.as_object().unwrap().clone());
+    serde_json::from_value(config).unwrap()
+}
+
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+/// Create an empty directory in the temp dir, named after the test.
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("semifold-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Create an empty repository in the temp dir, named after the test.
+pub(crate) fn temp_repo(name: &str) -> (PathBuf, Repository) {
+    let dir = temp_dir(name);
+    let repo = Repository::init(&dir).unwrap();
+    (dir, repo)
+}
+
+/// Commit changes of files, `None` contents remove the file.
+pub(crate) fn commit(repo: &Repository, files: &[(&str, Option<&str>)], message: &str) -> Oid {
+    commit_as(repo, "user", "user@example.com", files, message)
+}
+
+/// Commit changes of files by the given author.
+pub(crate) fn commit_as(
+    repo: &Repository,
+    name: &str,
+    email: &str,
+    files: &[(&str, Option<&str>)],
+    message: &str,
+) -> Oid {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for (path, content) in files {
+        match content {
+            Some(content) => {
+                std::fs::create_dir_all(workdir.join(path).parent().unwrap()).unwrap();
+                std::fs::write(workdir.join(path), content).unwrap();
+                index.add_path(Path::new(path)).unwrap();
+            }
+            None => {
+                std::fs::remove_file(workdir.join(path)).unwrap();
+                index.remove_path(Path::new(path)).unwrap();
+            }
+        }
+    }
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = git2::Signature::now(name, email).unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    repo.commit(
+        Some("HEAD"),
+        &sig,
+        &sig,
+        message,
+        &tree,
+        parent.as_ref().into_iter().collect::<Vec<_>>().as_slice(),
+    )
+    .unwrap()
+}
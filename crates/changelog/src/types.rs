@@ -1,4 +1,72 @@
+use semifold_resolver::utils::release_tag_name;
+use serde::Serialize;
+
 pub struct Changelog {
     pub version: String,
     pub body: String,
 }
+
+/// A parsed CHANGELOG.md file.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ParsedChangelog {
+    /// Content of the `[Unreleased]` section, if any.
+    pub unreleased: Option<Release>,
+    /// Releases, newest first.
+    pub releases: Vec<Release>,
+    /// Reference-style link definitions at the end of the file.
+    pub links: Vec<(String, String)>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Release {
+    /// Version without the `v` prefix, or the raw heading text when it is
+    /// not a version.
+    pub version: String,
+    /// Release date, formatted as `YYYY-MM-DD`.
+    pub date: Option<String>,
+    pub sections: Vec<Section>,
+    /// Raw markdown of the release, including its heading.
+    pub body: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Section {
+    /// Section title, `None` for entries before the first section.
+    pub title: Option<String>,
+    pub entries: Vec<String>,
+}
+
+/// Repository information used to build compare links between releases.
+pub struct CompareLinks {
+    /// Repository URL, e.g. `https://github.com/owner/repo`.
+    pub repo_url: String,
+    /// Package name, release tags are named `<package>-v<version>`.
+    pub package: String,
+}
+
+impl CompareLinks {
+    pub fn tag_name(&self, version: &str) -> String {
+        release_tag_name(&self.package, version)
+    }
+
+    /// Link of a release, compared to the previous one when there is one.
+    pub fn release_url(&self, version: &str, previous: Option<&str>) -> String {
+        match previous {
+            Some(previous) => format!(
+                "{}/compare/{}...{}",
+                self.repo_url,
+                self.tag_name(previous),
+                self.tag_name(version)
+            ),
+            None => format!("{}/releases/tag/{}", self.repo_url, self.tag_name(version)),
+        }
+    }
+
+    pub fn unreleased_url(&self, version: &str) -> String {
+        format!(
+            "{}/compare/{}...HEAD",
+            self.repo_url,
+            self.tag_name(version)
+        )
+    }
+}
@@ -1,125 +1,284 @@
-use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 
-use git2::{DiffOptions, Oid, Repository};
-use octocrab::Octocrab;
+use git2::{Delta, DiffOptions, Oid, Repository};
 
 use regex::Regex;
-use semifold_resolver::error::ResolveError;
+use semifold_resolver::{config::ChangelogFormat, error::ResolveError, transaction::Transaction};
+
+pub use semifold_resolver::utils::find_last_release_tag;
+
+use crate::types::CompareLinks;
+
+static LINK_DEFINITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[([^\]]+)\]:\s*(\S+)\s*$").unwrap());
 
 #[derive(Debug)]
 pub struct CommitInfo {
     pub oid: Oid,
     pub message: String,
+    /// Commit time in seconds since the Unix epoch.
+    pub time: i64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PrInfo {
     pub number: u64,
     pub author: Option<String>,
     pub url: Option<String>,
 }
 
-pub async fn query_pr_for_commit(
-    owner: &str,
-    repo: &str,
-    commit_info: &CommitInfo,
-) -> octocrab::Result<Option<PrInfo>> {
-    let octocrab = if let Ok(token) = std::env::var("GITHUB_TOKEN") {
-        Octocrab::builder().personal_token(token).build()?
-    } else {
-        Octocrab::builder().build()?
-    };
-
-    let prs = octocrab
-        .repos(owner, repo)
-        .list_pulls(commit_info.oid.to_string())
-        .send()
-        .await?;
+/// Introducing commits of changeset files, built with a single history walk.
+#[derive(Debug, Default)]
+pub struct CommitIndex {
+    commits: HashMap<PathBuf, CommitInfo>,
+    /// Commits of changesets derived from commits, which have no file.
+    derived: HashMap<String, CommitInfo>,
+}
 
-    if let Some(pr) = prs.items.into_iter().next() {
-        return Ok(Some(PrInfo {
-            number: pr.number,
-            author: pr.user.map(|u| u.login),
-            url: pr.html_url.map(|u| u.to_string()),
-        }));
+impl CommitIndex {
+    /// Index the first commits touching `paths` under `changeset_dir`.
+    ///
+    /// The walk stops at `since` when given, paths introduced before it are
+    /// looked up again in the full history.
+    pub fn build(
+        repo: &Repository,
+        changeset_dir: &Path,
+        paths: &[PathBuf],
+        since: Option<Oid>,
+    ) -> Result<Self, git2::Error> {
+        let mut index = Self::default();
+        let mut wanted = paths.iter().cloned().collect::<HashSet<_>>();
+        index.walk(repo, changeset_dir, &mut wanted, since)?;
+        if since.is_some() && !wanted.is_empty() {
+            log::debug!("Changesets introduced before {since:?}: {wanted:?}");
+            index.walk(repo, changeset_dir, &mut wanted, None)?;
+        }
+        Ok(index)
     }
 
-    let re = Regex::new(r"\(#(\d+)\)").unwrap();
-    if let Some(caps) = re.captures(&commit_info.message)
-        && let Ok(pr_number) = caps[1].parse::<u64>()
-    {
-        let pr = octocrab.pulls(owner, repo).get(pr_number).await?;
-        return Ok(Some(PrInfo {
-            number: pr.number,
-            author: pr.user.map(|u| u.login),
-            url: pr.html_url.map(|u| u.to_string()),
-        }));
-    }
+    fn walk(
+        &mut self,
+        repo: &Repository,
+        changeset_dir: &Path,
+        wanted: &mut HashSet<PathBuf>,
+        since: Option<Oid>,
+    ) -> Result<(), git2::Error> {
+        if wanted.is_empty() {
+            return Ok(());
+        }
 
-    Ok(None)
-}
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        if let Some(since) = since {
+            revwalk.hide(since)?;
+        }
+        // Parents always come first, commit times may tie or be skewed
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
 
-pub fn find_first_commit_for_path(repo: &Repository, path: &Path) -> Option<CommitInfo> {
-    let mut revwalk = repo.revwalk().ok()?;
-    revwalk.push_head().ok()?;
-    revwalk
-        .set_sorting(git2::Sort::TIME | git2::Sort::REVERSE)
-        .ok()?;
-
-    for oid in revwalk {
-        let oid = oid.ok()?;
-        let commit = repo.find_commit(oid).ok()?;
-        let tree = commit.tree().ok()?;
-
-        if commit.parent_count() == 0 {
-            if tree.get_path(std::path::Path::new(path)).is_ok() {
-                return Some(CommitInfo {
-                    oid,
-                    message: commit.message()?.to_string(),
-                });
-            }
-        } else {
-            let parent = commit.parent(0).ok()?;
-            let parent_tree = parent.tree().ok()?;
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            let tree = commit.tree()?;
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
 
             let mut diff_opts = DiffOptions::new();
-            diff_opts.pathspec(path);
+            diff_opts.pathspec(changeset_dir);
+            let diff =
+                repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut diff_opts))?;
 
-            let diff = repo
-                .diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(&mut diff_opts))
-                .ok()?;
-
-            if diff.deltas().len() > 0 {
-                return Some(CommitInfo {
-                    oid,
-                    message: commit.message()?.to_string(),
-                });
+            for delta in diff.deltas() {
+                if !matches!(
+                    delta.status(),
+                    Delta::Added | Delta::Renamed | Delta::Copied
+                ) {
+                    continue;
+                }
+                let Some(path) = delta.new_file().path() else {
+                    continue;
+                };
+                if wanted.remove(path) {
+                    self.commits.insert(
+                        path.to_path_buf(),
+                        CommitInfo {
+                            oid: commit.id(),
+                            message: commit.message().unwrap_or_default().to_string(),
+                            time: commit.time().seconds(),
+                        },
+                    );
+                }
+            }
+            if wanted.is_empty() {
+                break;
             }
         }
+
+        Ok(())
+    }
+
+    /// Record the commit of a changeset derived from it.
+    pub fn insert_commit(&mut self, commit: CommitInfo) {
+        self.derived.insert(commit.oid.to_string(), commit);
+    }
+
+    pub fn get_commit(&self, oid: &str) -> Option<&CommitInfo> {
+        self.derived.get(oid)
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&CommitInfo> {
+        self.commits.get(path)
+    }
+
+    pub fn commits(&self) -> impl Iterator<Item = &CommitInfo> {
+        self.commits.values().chain(self.derived.values())
     }
-    None
 }
 
-pub async fn insert_changelog<P: AsRef<Path>>(
-    path: P,
-    new_entry: &str,
-) -> Result<(), ResolveError> {
-    let path = path.as_ref();
-    let header = "# Changelog";
+/// Header of new changelog files in the Keep a Changelog format.
+const KEEP_A_CHANGELOG_PREAMBLE: &str = "\
+All notable changes to this project will be documented in this file.
 
-    let content = if path.exists() {
-        std::fs::read_to_string(path)?
-    } else {
-        format!("{header}\n\n")
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]";
+
+/// Parse the version of a release heading, such as `## v1.2.0` or
+/// `## [1.2.0] - 2026-10-18`.
+///
+/// Returns `None` for other lines and for the `[Unreleased]` heading.
+pub fn parse_release_heading(line: &str) -> Option<String> {
+    let rest = line.trim().strip_prefix("## ")?.trim();
+    let version = match rest.strip_prefix('[') {
+        Some(rest) => &rest[..rest.find(']')?],
+        None => rest.split_whitespace().next()?,
     };
+    if version.eq_ignore_ascii_case("unreleased") {
+        return None;
+    }
+    Some(version.strip_prefix('v').unwrap_or(version).to_string())
+}
 
-    let insert_pos = content.find(header).ok_or(ResolveError::InvalidChangelog {
-        path: path.to_path_buf(),
-        reason: "No `# Changelog` header found in file".to_string(),
-    })?;
+fn is_unreleased_heading(line: &str) -> bool {
+    line.trim()
+        .strip_prefix("## ")
+        .map(|rest| rest.trim().trim_start_matches('[').trim_end_matches(']'))
+        .is_some_and(|rest| rest.eq_ignore_ascii_case("unreleased"))
+}
+
+/// Parse a reference-style link definition, e.g. `[1.2.0]: https://...`.
+fn parse_link_definition(line: &str) -> Option<(String, String)> {
+    let caps = LINK_DEFINITION.captures(line.trim())?;
+    Some((caps[1].to_string(), caps[2].to_string()))
+}
+
+/// Split the link definitions at the end of a changelog from its content.
+pub fn split_link_definitions(content: &str) -> (Vec<&str>, Vec<(String, String)>) {
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut end = lines.len();
+    while end > 0
+        && (lines[end - 1].trim().is_empty() || parse_link_definition(lines[end - 1]).is_some())
+    {
+        end -= 1;
+    }
+    let definitions = lines[end..]
+        .iter()
+        .filter_map(|line| parse_link_definition(line))
+        .collect();
+    (lines[..end].to_vec(), definitions)
+}
+
+fn insert_keep_a_changelog(content: &str, new_entry: &str, links: Option<&CompareLinks>) -> String {
+    let (lines, mut definitions) = split_link_definitions(content);
+    let is_heading = |line: &&str| line.trim_start().starts_with("## ");
 
-    let after_header_pos = insert_pos + header.len();
+    // New releases go right after the unreleased section, which is kept as is
+    let unreleased = lines.iter().position(|line| is_unreleased_heading(line));
+    let search_from = unreleased.map_or(0, |idx| idx + 1);
+    let insert_at = lines[search_from..]
+        .iter()
+        .position(is_heading)
+        .map_or(lines.len(), |idx| idx + search_from);
+    let previous = lines[insert_at..]
+        .iter()
+        .find_map(|line| parse_release_heading(line));
 
+    let new_entry = new_entry.trim();
+    let mut new_content = lines[..insert_at].join("\n").trim_end().to_string();
+    if unreleased.is_none() {
+        new_content.push_str("\n\n## [Unreleased]");
+    }
+    new_content.push_str("\n\n");
+    new_content.push_str(new_entry);
+    let after = lines[insert_at..].join("\n");
+    if !after.trim().is_empty() {
+        new_content.push_str("\n\n");
+        new_content.push_str(after.trim());
+    }
+
+    if let Some(links) = links
+        && let Some(version) = new_entry.lines().next().and_then(parse_release_heading)
+    {
+        definitions
+            .retain(|(label, _)| !label.eq_ignore_ascii_case("unreleased") && *label != version);
+        definitions.insert(
+            0,
+            (
+                version.clone(),
+                links.release_url(&version, previous.as_deref()),
+            ),
+        );
+        definitions.insert(
+            0,
+            ("Unreleased".to_string(), links.unreleased_url(&version)),
+        );
+    }
+    if !definitions.is_empty() {
+        let definitions = definitions
+            .iter()
+            .map(|(label, url)| format!("[{label}]: {url}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        new_content.push_str("\n\n");
+        new_content.push_str(&definitions);
+    }
+    new_content.push('\n');
+    new_content
+}
+
+const CHANGELOG_HEADER: &str = "# Changelog";
+
+/// Content of a new changelog file without releases.
+pub fn empty_changelog(format: ChangelogFormat) -> String {
+    match format {
+        ChangelogFormat::Default => format!("{CHANGELOG_HEADER}\n\n"),
+        ChangelogFormat::KeepAChangelog => {
+            format!("{CHANGELOG_HEADER}\n\n{KEEP_A_CHANGELOG_PREAMBLE}\n")
+        }
+    }
+}
+
+/// Insert a release entry into the content of a changelog.
+///
+/// Returns `None` if the content has no `# Changelog` header.
+pub fn insert_changelog_entry(
+    content: &str,
+    new_entry: &str,
+    format: ChangelogFormat,
+    links: Option<&CompareLinks>,
+) -> Option<String> {
+    let insert_pos = content.find(CHANGELOG_HEADER)?;
+
+    if format == ChangelogFormat::KeepAChangelog {
+        return Some(insert_keep_a_changelog(content, new_entry, links));
+    }
+
+    let after_header_pos = insert_pos + CHANGELOG_HEADER.len();
     let before = &content[..after_header_pos].trim_end_matches('\n');
     let after = &content[after_header_pos..].trim_start_matches('\n');
     let new_entry = new_entry.trim();
@@ -133,7 +292,152 @@ pub async fn insert_changelog<P: AsRef<Path>>(
         new_content.push_str(after.trim_end_matches('\n'));
     }
     new_content.push('\n');
+    Some(new_content)
+}
 
-    std::fs::write(path, new_content)?;
+/// Stage a release entry into a changelog file, creating it when missing.
+///
+/// Compare links are only maintained in the Keep a Changelog format.
+pub async fn insert_changelog<P: AsRef<Path>>(
+    transaction: &Transaction,
+    path: P,
+    new_entry: &str,
+    format: ChangelogFormat,
+    links: Option<&CompareLinks>,
+) -> Result<(), ResolveError> {
+    let path = path.as_ref();
+    let content = if transaction.exists(path) {
+        transaction.read_to_string(path)?
+    } else {
+        empty_changelog(format)
+    };
+
+    let new_content = insert_changelog_entry(&content, new_entry, format, links).ok_or(
+        ResolveError::InvalidChangelog {
+            path: path.to_path_buf(),
+            reason: "No `# Changelog` header found in file".to_string(),
+        },
+    )?;
+    transaction.write(path, new_content);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit, temp_repo};
+
+    #[test]
+    fn indexes_changesets_since_last_release() {
+        let (dir, repo) = temp_repo("commit-index");
+
+        let old = commit(&repo, &[(".changes/old.md", Some("old"))], "add old");
+        let first = commit(
+            &repo,
+            &[(".changes/readded.md", Some("first"))],
+            "add readded",
+        );
+        commit(&repo, &[(".changes/readded.md", None)], "release");
+        let release = repo.head().unwrap().peel_to_commit().unwrap().id();
+        let latest = commit(
+            &repo,
+            &[
+                (".changes/readded.md", Some("again")),
+                (".changes/new.md", Some("new")),
+                ("src/lib.rs", Some("")),
+            ],
+            "add new",
+        );
+
+        let changeset_dir = Path::new(".changes");
+        let paths = ["old.md", "readded.md", "new.md"].map(|name| changeset_dir.join(name));
+        let oid =
+            |index: &CommitIndex, name: &str| index.get(&changeset_dir.join(name)).unwrap().oid;
+
+        // Changesets added since the release are found in the bounded walk,
+        // older ones are looked up in the full history
+        let index = CommitIndex::build(&repo, changeset_dir, &paths, Some(release)).unwrap();
+        assert_eq!(oid(&index, "new.md"), latest);
+        assert_eq!(oid(&index, "readded.md"), latest);
+        assert_eq!(oid(&index, "old.md"), old);
+
+        let index = CommitIndex::build(&repo, changeset_dir, &paths, None).unwrap();
+        assert_eq!(oid(&index, "readded.md"), first);
+        assert_eq!(index.commits().count(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn links() -> CompareLinks {
+        CompareLinks {
+            repo_url: "https://github.com/o/r".to_string(),
+            package: "a".to_string(),
+        }
+    }
+
+    fn insert(content: &str, entry: &str) -> String {
+        insert_changelog_entry(
+            content,
+            entry,
+            ChangelogFormat::KeepAChangelog,
+            Some(&links()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn inserts_first_keep_a_changelog_release() {
+        let content = empty_changelog(ChangelogFormat::KeepAChangelog);
+        let content = insert(
+            &content,
+            "## [1.0.0] - 2026-10-18\n\n### Added\n\n- Initial\n",
+        );
+        assert_eq!(
+            content,
+            format!(
+                "# Changelog\n\n{KEEP_A_CHANGELOG_PREAMBLE}\n\n\
+                 ## [1.0.0] - 2026-10-18\n\n### Added\n\n- Initial\n\n\
+                 [Unreleased]: https://github.com/o/r/compare/a-v1.0.0...HEAD\n\
+                 [1.0.0]: https://github.com/o/r/releases/tag/a-v1.0.0\n"
+            )
+        );
+    }
+
+    #[test]
+    fn inserts_later_keep_a_changelog_release_after_unreleased() {
+        let content = "# Changelog\n\n## [Unreleased]\n\n- Pending\n\n\
+                       ## [1.0.0] - 2026-10-18\n\n- Initial\n\n\
+                       [unreleased]: https://github.com/o/r/compare/a-v1.0.0...HEAD\n\
+                       [1.0.0]: https://github.com/o/r/releases/tag/a-v1.0.0\n";
+        let content = insert(content, "## [1.1.0] - 2026-10-19\n\n- Feature");
+        assert_eq!(
+            content,
+            "# Changelog\n\n## [Unreleased]\n\n- Pending\n\n\
+             ## [1.1.0] - 2026-10-19\n\n- Feature\n\n\
+             ## [1.0.0] - 2026-10-18\n\n- Initial\n\n\
+             [Unreleased]: https://github.com/o/r/compare/a-v1.1.0...HEAD\n\
+             [1.1.0]: https://github.com/o/r/compare/a-v1.0.0...a-v1.1.0\n\
+             [1.0.0]: https://github.com/o/r/releases/tag/a-v1.0.0\n"
+        );
+    }
+
+    #[test]
+    fn updates_unreleased_compare_link() {
+        let content = insert(
+            &empty_changelog(ChangelogFormat::KeepAChangelog),
+            "## [1.0.0] - 2026-10-18\n\n- Initial",
+        );
+        let content = insert(&content, "## [1.0.1] - 2026-10-19\n\n- Fix");
+        let (_, definitions) = split_link_definitions(&content);
+        let unreleased = definitions
+            .iter()
+            .filter(|(label, _)| label.eq_ignore_ascii_case("unreleased"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unreleased,
+            [&(
+                "Unreleased".to_string(),
+                "https://github.com/o/r/compare/a-v1.0.1...HEAD".to_string()
+            )]
+        );
+    }
+}
@@ -1,47 +1,87 @@
 use core::fmt;
-use std::path::{Path, PathBuf};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
 
-use saphyr::{LoadableYamlNode, Mapping, Yaml, YamlEmitter};
+use saphyr::{LoadableYamlNode, Mapping, Scalar, Yaml, YamlEmitter};
 use serde::{Deserialize, Serialize};
 
-use crate::{context::Context, error::ResolveError};
+use crate::{context::Context, error::ResolveError, transaction::Transaction};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 pub enum BumpLevel {
+    /// Set the package to an explicit version.
+    Exact = 4,
     Major = 3,
     Minor = 2,
     Patch = 1,
+    /// No version bump, the change is only noted in the changelog.
+    #[serde(rename = "none", alias = "unchanged")]
     Unchanged = 0,
 }
 
 impl fmt::Display for BumpLevel {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            BumpLevel::Exact => write!(f, "exact"),
             BumpLevel::Major => write!(f, "major"),
             BumpLevel::Minor => write!(f, "minor"),
             BumpLevel::Patch => write!(f, "patch"),
-            BumpLevel::Unchanged => write!(f, "unchanged"),
+            BumpLevel::Unchanged => write!(f, "none"),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChangePackage {
     pub name: String,
     pub level: BumpLevel,
     pub tag: Option<String>,
+    /// Explicit version, only set for [`BumpLevel::Exact`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<semver::Version>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl ChangePackage {
+    /// Level part of the package mark, e.g. `minor` or `=2.0.0`.
+    pub fn mark(&self) -> String {
+        match (&self.level, &self.version) {
+            (BumpLevel::Exact, Some(version)) => format!("={version}"),
+            (level, _) => level.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Changeset {
     pub name: String,
     pub packages: Vec<ChangePackage>,
     pub summary: String,
     pub root_path: PathBuf,
     pub path: Option<PathBuf>,
+    /// Issues fixed by the changeset.
+    #[serde(default)]
+    pub issues: Vec<u64>,
+    /// Breaking change or migration note.
+    #[serde(default)]
+    pub breaking: Option<String>,
+    /// GitHub usernames of the authors.
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// Number of the pull request that introduced the changeset.
+    #[serde(default)]
+    pub pr: Option<u64>,
+    /// Commit the changeset is derived from, for changesets without a file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
 }
 
+/// Front matter keys reserved for changeset metadata, which cannot be used as
+/// package names.
+pub const METADATA_KEYS: [&str; 4] = ["issues", "breaking", "authors", "pr"];
+
 impl Changeset {
     pub fn new(name: String, root_path: &Path) -> Self {
         Self {
@@ -50,12 +90,37 @@ impl Changeset {
             summary: String::new(),
             root_path: root_path.to_path_buf(),
             path: None,
+            issues: Vec::new(),
+            breaking: None,
+            authors: Vec::new(),
+            pr: None,
+            commit: None,
         }
     }
 
     #[inline]
     pub fn add_package(&mut self, name: String, level: BumpLevel, tag: Option<String>) {
-        self.packages.push(ChangePackage { name, level, tag })
+        self.packages.push(ChangePackage {
+            name,
+            level,
+            tag,
+            version: None,
+        })
+    }
+
+    #[inline]
+    pub fn add_package_version(
+        &mut self,
+        name: String,
+        version: semver::Version,
+        tag: Option<String>,
+    ) {
+        self.packages.push(ChangePackage {
+            name,
+            level: BumpLevel::Exact,
+            tag,
+            version: Some(version),
+        })
     }
 
     pub fn add_packages(&mut self, packages: &[String], level: BumpLevel, tag: Option<String>) {
@@ -68,7 +133,36 @@ impl Changeset {
         self.summary = summary;
     }
 
-    pub fn from_file(ctx: &Context, path: &PathBuf) -> Result<Self, ResolveError> {
+    /// Hash of the rendered changeset, which changes with any edit of its
+    /// packages, metadata or summary.
+    pub fn content_hash(&self) -> Result<String, ResolveError> {
+        let content = self.render(self.path.as_deref().unwrap_or(&self.root_path))?;
+        let oid =
+            git2::Oid::hash_object(git2::ObjectType::Blob, content.as_bytes()).map_err(|e| {
+                ResolveError::GitError {
+                    message: e.message().to_string(),
+                }
+            })?;
+        Ok(oid.to_string())
+    }
+
+    pub fn from_file(ctx: &Context, path: &Path) -> Result<Self, ResolveError> {
+        let changeset = Self::parse_file(path)?;
+        if let Some(package) = changeset
+            .packages
+            .iter()
+            .find(|p| !ctx.has_package(&p.name))
+        {
+            return Err(ResolveError::InvalidChangeset {
+                path: path.to_path_buf(),
+                reason: format!("Package {} is not defined in config", package.name),
+            });
+        }
+        Ok(changeset)
+    }
+
+    /// Parse a changeset file without validating it against the config.
+    pub fn parse_file(path: &Path) -> Result<Self, ResolveError> {
         let changeset_str = std::fs::read_to_string(path)?;
         let separator = "---";
 
@@ -86,7 +180,20 @@ impl Changeset {
         })?;
         let packages_map = fm.first().and_then(|f| f.as_mapping());
 
+        // Duplicated keys are silently merged by the YAML loader, so they are
+        // detected on the raw front matter instead.
+        if let Some(name) = find_duplicate_key(left_part) {
+            return Err(ResolveError::InvalidChangeset {
+                path: path.to_path_buf(),
+                reason: format!("Package {name} is specified more than once"),
+            });
+        }
+
         let mut packages = Vec::new();
+        let mut issues = Vec::new();
+        let mut breaking = None;
+        let mut authors = Vec::new();
+        let mut pr = None;
         if let Some(map) = packages_map {
             map.into_iter().try_for_each(|(k, v)| {
                 let name = k
@@ -96,11 +203,38 @@ impl Changeset {
                         reason: format!("Failed to parse package name: {k:?}"),
                     })?
                     .to_string();
-                if !ctx.has_package(&name) {
-                    return Err(ResolveError::InvalidChangeset {
-                        path: path.to_path_buf(),
-                        reason: format!("Package {name} is not defined in config"),
-                    });
+
+                match name.as_str() {
+                    "issues" => {
+                        issues = parse_issues(v).ok_or(ResolveError::InvalidChangeset {
+                            path: path.to_path_buf(),
+                            reason: format!("Failed to parse issues: {v:?}"),
+                        })?;
+                        return Ok(());
+                    }
+                    "breaking" => {
+                        let note = v.as_str().ok_or(ResolveError::InvalidChangeset {
+                            path: path.to_path_buf(),
+                            reason: format!("Failed to parse breaking note: {v:?}"),
+                        })?;
+                        breaking = Some(note.trim().to_string()).filter(|n| !n.is_empty());
+                        return Ok(());
+                    }
+                    "authors" => {
+                        authors = parse_authors(v).ok_or(ResolveError::InvalidChangeset {
+                            path: path.to_path_buf(),
+                            reason: format!("Failed to parse authors: {v:?}"),
+                        })?;
+                        return Ok(());
+                    }
+                    "pr" => {
+                        pr = Some(parse_number(v).ok_or(ResolveError::InvalidChangeset {
+                            path: path.to_path_buf(),
+                            reason: format!("Failed to parse pull request number: {v:?}"),
+                        })?);
+                        return Ok(());
+                    }
+                    _ => {}
                 }
 
                 let mark = v
@@ -116,10 +250,21 @@ impl Changeset {
                     reason: format!("Failed to parse package mark: {v:?}"),
                 })?;
                 let tag = mark.next().map(|s| s.to_string());
+                let mut version = None;
                 let level = match level {
                     "major" => BumpLevel::Major,
                     "minor" => BumpLevel::Minor,
                     "patch" => BumpLevel::Patch,
+                    "none" => BumpLevel::Unchanged,
+                    _ if level.starts_with('=') => {
+                        version = Some(semver::Version::parse(level[1..].trim()).map_err(|e| {
+                            ResolveError::InvalidChangeset {
+                                path: path.to_path_buf(),
+                                reason: format!("Invalid explicit version {level}: {e}"),
+                            }
+                        })?);
+                        BumpLevel::Exact
+                    }
                     _ => {
                         return Err(ResolveError::InvalidChangeset {
                             path: path.to_path_buf(),
@@ -127,7 +272,12 @@ impl Changeset {
                         });
                     }
                 };
-                packages.push(ChangePackage { name, level, tag });
+                packages.push(ChangePackage {
+                    name,
+                    level,
+                    tag,
+                    version,
+                });
                 Ok(())
             })?;
         }
@@ -147,22 +297,24 @@ impl Changeset {
             summary,
             root_path: path.parent().unwrap().to_path_buf(),
             path: Some(path.to_path_buf()),
+            issues,
+            breaking,
+            authors,
+            pr,
+            commit: None,
         })
     }
 
-    pub fn commit_to(&mut self, changeset_path: &Path) -> Result<(), ResolveError> {
-        log::debug!("Commit changeset: {self:?}");
-
-        let file_path = changeset_path.join(format!("{}.md", self.name));
-
+    /// Render the changeset file at `file_path`.
+    fn render(&self, file_path: &Path) -> Result<String, ResolveError> {
         let mut fm = String::new();
         let mut emitter = YamlEmitter::new(&mut fm);
         let mut fm_map = Mapping::new();
         for package in &self.packages {
             let mark = if let Some(tag) = &package.tag {
-                format!("{}:{}", package.level, tag)
+                format!("{}:{}", package.mark(), tag)
             } else {
-                format!("{}", package.level)
+                package.mark()
             };
 
             fm_map.insert(
@@ -170,21 +322,68 @@ impl Changeset {
                 Yaml::value_from_str(mark.leak()),
             );
         }
+        if !self.issues.is_empty() {
+            fm_map.insert(
+                Yaml::value_from_str("issues"),
+                Yaml::Sequence(
+                    self.issues
+                        .iter()
+                        .map(|issue| Yaml::Value(Scalar::Integer(*issue as i64)))
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(breaking) = &self.breaking {
+            fm_map.insert(
+                Yaml::value_from_str("breaking"),
+                Yaml::Value(Scalar::String(Cow::Owned(breaking.clone()))),
+            );
+        }
+        if let Some(pr) = self.pr {
+            fm_map.insert(
+                Yaml::value_from_str("pr"),
+                Yaml::Value(Scalar::Integer(pr as i64)),
+            );
+        }
+        if !self.authors.is_empty() {
+            fm_map.insert(
+                Yaml::value_from_str("authors"),
+                Yaml::Sequence(
+                    self.authors
+                        .iter()
+                        .map(|author| Yaml::Value(Scalar::String(Cow::Owned(author.clone()))))
+                        .collect(),
+                ),
+            );
+        }
         emitter
             .dump(&Yaml::Mapping(fm_map))
             .map_err(|e| ResolveError::ParseError {
-                path: file_path.clone(),
+                path: file_path.to_path_buf(),
                 reason: e.to_string(),
             })?;
 
-        let content = format!("{fm}\n---\n\n{}\n", self.summary);
-        std::fs::write(&file_path, content)?;
+        Ok(format!("{fm}\n---\n\n{}\n", self.summary))
+    }
+
+    pub fn commit_to(&mut self, changeset_path: &Path) -> Result<(), ResolveError> {
+        log::debug!("Commit changeset: {self:?}");
 
+        let file_path = changeset_path.join(format!("{}.md", self.name));
+        std::fs::write(&file_path, self.render(&file_path)?)?;
         self.path = Some(file_path);
 
         Ok(())
     }
 
+    /// Stage the changeset file into a transaction, like [`Changeset::commit`].
+    pub fn commit_in(&mut self, transaction: &Transaction) -> Result<(), ResolveError> {
+        let file_path = self.root_path.join(format!("{}.md", self.name));
+        transaction.write(&file_path, self.render(&file_path)?);
+        self.path = Some(file_path);
+        Ok(())
+    }
+
     pub fn commit(&mut self) -> Result<(), ResolveError> {
         self.commit_to(&self.root_path.clone())
     }
@@ -194,4 +393,162 @@ impl Changeset {
         std::fs::remove_file(file_path)?;
         Ok(())
     }
+
+    /// Stage the removal of the changeset file into a transaction.
+    pub fn clean_in(&self, transaction: &Transaction) {
+        transaction.remove(self.root_path.join(format!("{}.md", self.name)));
+    }
+}
+
+/// Parse an issue or pull request number, given either as an integer or a
+/// `#123` string.
+fn parse_number(value: &Yaml) -> Option<u64> {
+    value
+        .as_integer()
+        .and_then(|n| u64::try_from(n).ok())
+        .or_else(|| value.as_str()?.trim().trim_start_matches('#').parse().ok())
+}
+
+/// Parse issue numbers, given either as a single number or a list.
+fn parse_issues(value: &Yaml) -> Option<Vec<u64>> {
+    match value.as_vec() {
+        Some(items) => items.iter().map(parse_number).collect(),
+        None => parse_number(value).map(|issue| vec![issue]),
+    }
+}
+
+/// Parse author names, with or without the leading `@`.
+fn parse_authors(value: &Yaml) -> Option<Vec<String>> {
+    let parse_one = |item: &Yaml| {
+        item.as_str()
+            .map(|author| author.trim().trim_start_matches('@').to_string())
+    };
+    match value.as_vec() {
+        Some(items) => items.iter().map(parse_one).collect(),
+        None => parse_one(value).map(|author| vec![author]),
+    }
+}
+
+/// Find the first top-level key that appears more than once in a front matter.
+fn find_duplicate_key(front_matter: &str) -> Option<String> {
+    let mut keys = Vec::new();
+    for line in front_matter.lines() {
+        if line.is_empty() || line.starts_with([' ', '\t', '#', '-']) {
+            continue;
+        }
+        let key = Yaml::load_from_str(line).ok().and_then(|docs| {
+            docs.first()
+                .and_then(|doc| doc.as_mapping())
+                .and_then(|map| map.keys().next())
+                .and_then(|key| key.as_str().map(|k| k.to_string()))
+        });
+        if let Some(key) = key {
+            if keys.contains(&key) {
+                return Some(key);
+            }
+            keys.push(key);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn finds_duplicate_keys() {
+        assert_eq!(
+            find_duplicate_key("a: patch\nb: minor\na: major\n"),
+            Some("a".to_string())
+        );
+        assert_eq!(
+            find_duplicate_key("\"a\": patch\n'a': minor\n"),
+            Some("a".to_string())
+        );
+        assert_eq!(find_duplicate_key("a: patch\nb: minor\n"), None);
+    }
+
+    #[test]
+    fn ignores_nested_and_comment_lines() {
+        let front_matter = "---\na: patch\nissues:\n  - 1\n  - 1\n# a: minor\nauthors:\n- foo\n";
+        assert_eq!(find_duplicate_key(front_matter), None);
+    }
+
+    #[test]
+    fn round_trips_metadata() {
+        let dir = temp_dir("changeset");
+
+        let mut changeset = Changeset::new("add-feature".to_string(), &dir);
+        changeset.add_package(
+            "core".to_string(),
+            BumpLevel::Minor,
+            Some("feat".to_string()),
+        );
+        changeset.add_package_version("cli".to_string(), semver::Version::new(2, 0, 0), None);
+        changeset.add_package("docs".to_string(), BumpLevel::Unchanged, None);
+        changeset.issues = vec![12, 34];
+        changeset.breaking = Some("Options are renamed: `foo` is now `bar`.".to_string());
+        changeset.authors = vec!["alice".to_string(), "bob".to_string()];
+        changeset.pr = Some(56);
+        changeset.summary("Add a feature".to_string());
+        changeset.commit().unwrap();
+
+        let parsed = Changeset::parse_file(changeset.path.as_ref().unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(parsed.name, "add-feature");
+        assert_eq!(
+            parsed
+                .packages
+                .iter()
+                .map(|p| (p.name.as_str(), p.mark(), p.tag.as_deref()))
+                .collect::<Vec<_>>(),
+            [
+                ("core", "minor".to_string(), Some("feat")),
+                ("cli", "=2.0.0".to_string(), None),
+                ("docs", "none".to_string(), None),
+            ]
+        );
+        assert_eq!(parsed.issues, changeset.issues);
+        assert_eq!(parsed.breaking, changeset.breaking);
+        assert_eq!(parsed.authors, changeset.authors);
+        assert_eq!(parsed.pr, changeset.pr);
+        assert_eq!(parsed.summary, "Add a feature");
+    }
+
+    #[test]
+    fn parses_metadata_shorthands() {
+        let dir = temp_dir("shorthand");
+        let path = dir.join("fix.md");
+        std::fs::write(
+            &path,
+            "---\ncore: patch\nissues: \"#7\"\nauthors: \"@alice\"\npr: \"#8\"\n---\n\nFix a bug\n",
+        )
+        .unwrap();
+
+        let parsed = Changeset::parse_file(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(parsed.issues, [7]);
+        assert_eq!(parsed.authors, ["alice"]);
+        assert_eq!(parsed.pr, Some(8));
+        assert_eq!(parsed.breaking, None);
+    }
+
+    #[test]
+    fn hashes_changeset_content() {
+        let mut changeset = Changeset::new("fix".to_string(), Path::new(".changes"));
+        changeset.add_package("core".to_string(), BumpLevel::Patch, None);
+        changeset.summary("Fix a bug".to_string());
+        let hash = changeset.content_hash().unwrap();
+        assert_eq!(changeset.clone().content_hash().unwrap(), hash);
+
+        let mut edited = changeset.clone();
+        edited.summary("Fix another bug".to_string());
+        assert_ne!(edited.content_hash().unwrap(), hash);
+
+        let mut bumped = changeset.clone();
+        bumped.packages[0].level = BumpLevel::Minor;
+        assert_ne!(bumped.content_hash().unwrap(), hash);
+    }
 }
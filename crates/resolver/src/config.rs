@@ -4,6 +4,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{error::ResolveError, resolver};
@@ -62,6 +63,10 @@ pub struct PackageConfig {
     /// Assets to publish.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub assets: Vec<Asset>,
+    /// List contributors at the end of changelog entries, overriding
+    /// `changelog.contributors`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contributors: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
@@ -90,7 +95,7 @@ impl From<StdioType> for std::process::Stdio {
 }
 
 /// Configuration for a command to run.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CommandConfig {
     /// Executable command to run.
     pub command: String,
@@ -114,7 +119,7 @@ pub struct CommandConfig {
     pub dry_run: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PreCheckConfig {
     pub url: String,
     #[serde(
@@ -125,7 +130,7 @@ pub struct PreCheckConfig {
     pub extra_headers: BTreeMap<String, String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ResolverConfig {
     /// Pre-check configuration.
     #[serde(rename = "pre-check")]
@@ -143,18 +148,221 @@ pub struct ResolverConfig {
         skip_serializing_if = "Vec::is_empty"
     )]
     pub post_version: Vec<CommandConfig>,
+    /// Arguments appended to publish commands when publishing with a dist-tag,
+    /// `{tag}` is replaced with the tag name.
+    #[serde(
+        default,
+        rename = "dist-tag-args",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub dist_tag_args: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SnapshotConfig {
+    /// Use `<next>-<name>.<sha>` instead of `0.0.0-<name>-<timestamp>` for snapshot versions.
+    #[serde(default, rename = "use-calculated-version")]
+    pub use_calculated_version: bool,
+}
+
+impl SnapshotConfig {
+    pub fn is_default(&self) -> bool {
+        !self.use_calculated_version
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TagConfig {
+    /// Section title of the tag.
+    Title(String),
+    Detailed {
+        /// Section title of the tag.
+        title: String,
+        /// Leave changes of the tag out of changelogs.
+        #[serde(default)]
+        hidden: bool,
+    },
+}
+
+impl TagConfig {
+    pub fn title(&self) -> &str {
+        match self {
+            TagConfig::Title(title) => title,
+            TagConfig::Detailed { title, .. } => title,
+        }
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        matches!(self, TagConfig::Detailed { hidden: true, .. })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionPosition {
+    First,
+    #[default]
+    Last,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangelogFormat {
+    /// `## v1.2.0` headings, newest release first.
+    #[default]
+    Default,
+    /// [Keep a Changelog](https://keepachangelog.com) layout with dates, an
+    /// `[Unreleased]` section and compare links.
+    KeepAChangelog,
+}
+
+/// Where version bumps come from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VersionSource {
+    /// Changeset files in the changeset directory.
+    #[default]
+    Changesets,
+    /// Conventional commits since the last release tag of each package.
+    Commits,
+}
+
+impl VersionSource {
+    pub fn is_changesets(&self) -> bool {
+        matches!(self, Self::Changesets)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChangelogConfig {
+    /// Layout of the changelog files.
+    #[serde(default)]
+    pub format: ChangelogFormat,
+    /// Section title of untagged changes.
+    #[serde(
+        default = "ChangelogConfig::default_untagged_title",
+        rename = "untagged-title"
+    )]
+    pub untagged_title: String,
+    /// Position of the untagged section relative to the tag sections.
+    #[serde(default, rename = "untagged-position")]
+    pub untagged_position: SectionPosition,
+    /// Section title of internal dependencies bumped in the same run.
+    #[serde(
+        default = "ChangelogConfig::default_dependencies_title",
+        rename = "dependencies-title"
+    )]
+    pub dependencies_title: String,
+    /// Omit the section of internal dependency updates.
+    #[serde(default, rename = "hide-dependencies")]
+    pub hide_dependencies: bool,
+    /// List contributors at the end of changelog entries, highlighting new
+    /// contributors.
+    #[serde(default)]
+    pub contributors: bool,
+    /// Section title of the contributors.
+    #[serde(
+        default = "ChangelogConfig::default_contributors_title",
+        rename = "contributors-title"
+    )]
+    pub contributors_title: String,
+    /// Section title of first-time contributors.
+    #[serde(
+        default = "ChangelogConfig::default_new_contributors_title",
+        rename = "new-contributors-title"
+    )]
+    pub new_contributors_title: String,
+    /// Template of the release header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    /// Template of the section headers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Template of the change lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<String>,
+    /// Path of the aggregated changelog of all packages released together,
+    /// relative to the repository root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+}
+
+impl ChangelogConfig {
+    fn default_untagged_title() -> String {
+        "Changes".to_string()
+    }
+
+    fn default_dependencies_title() -> String {
+        "Updated Dependencies".to_string()
+    }
+
+    fn default_contributors_title() -> String {
+        "Contributors".to_string()
+    }
+
+    fn default_new_contributors_title() -> String {
+        "New Contributors".to_string()
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.format == ChangelogFormat::default()
+            && self.untagged_title == Self::default_untagged_title()
+            && self.untagged_position == SectionPosition::default()
+            && self.dependencies_title == Self::default_dependencies_title()
+            && !self.hide_dependencies
+            && !self.contributors
+            && self.contributors_title == Self::default_contributors_title()
+            && self.new_contributors_title == Self::default_new_contributors_title()
+            && self.header.is_none()
+            && self.section.is_none()
+            && self.line.is_none()
+            && self.root.is_none()
+    }
+}
+
+impl Default for ChangelogConfig {
+    fn default() -> Self {
+        Self {
+            format: ChangelogFormat::default(),
+            untagged_title: Self::default_untagged_title(),
+            untagged_position: SectionPosition::default(),
+            dependencies_title: Self::default_dependencies_title(),
+            hide_dependencies: false,
+            contributors: false,
+            contributors_title: Self::default_contributors_title(),
+            new_contributors_title: Self::default_new_contributors_title(),
+            header: None,
+            section: None,
+            line: None,
+            root: None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     /// Branch configuration.
     pub branches: BranchesConfig,
-    /// Tag configuration.
-    pub tags: BTreeMap<String, String>,
+    /// Tag configuration, changelog sections follow its order.
+    pub tags: IndexMap<String, TagConfig>,
     /// Package configuration.
     pub packages: BTreeMap<String, PackageConfig>,
     /// Resolver configuration.
     pub resolver: BTreeMap<resolver::ResolverType, ResolverConfig>,
+    /// Snapshot release configuration.
+    #[serde(default, skip_serializing_if = "SnapshotConfig::is_default")]
+    pub snapshot: SnapshotConfig,
+    /// Changelog configuration.
+    #[serde(default, skip_serializing_if = "ChangelogConfig::is_default")]
+    pub changelog: ChangelogConfig,
+    /// Source of version bumps.
+    #[serde(
+        default,
+        rename = "version-source",
+        skip_serializing_if = "VersionSource::is_changesets"
+    )]
+    pub version_source: VersionSource,
 }
 
 pub fn get_config_path(changeset_path: &Path) -> Result<PathBuf, ResolveError> {
@@ -1,11 +1,11 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     env,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
-use crate::{config, error, resolver};
+use crate::{config, error, pre, resolver, transaction};
 
 #[derive(Debug)]
 pub struct RepoInfo {
@@ -21,8 +21,13 @@ pub struct Context {
     pub repo_root: Option<PathBuf>,
     pub repo_info: Option<RepoInfo>,
     pub git_repo: Option<git2::Repository>,
+    pub pre_state: Option<pre::PreState>,
     pub version_bumps: RefCell<HashMap<String, semver::Version>>,
     pub dry_run: bool,
+    /// Name of the snapshot release, if any.
+    pub snapshot: Option<String>,
+    /// Writes of the run, committed together once all of them succeed.
+    pub transaction: transaction::Transaction,
 }
 
 impl Context {
@@ -38,6 +43,11 @@ impl Context {
         } else {
             None
         };
+        let pre_state = if let Some(changeset_root) = &changeset_root {
+            pre::PreState::load(changeset_root)?
+        } else {
+            None
+        };
         let repo_root = resolver::get_repo_root()
             .ok()
             .and_then(|path| path.parent().map(|p| p.to_path_buf()));
@@ -60,6 +70,7 @@ impl Context {
             repo_root,
             repo_info,
             git_repo,
+            pre_state,
             ..Default::default()
         })
     }
@@ -169,7 +180,137 @@ impl Context {
         Ok(assets)
     }
 
+    /// Get files changed in the working tree since the merge base of `HEAD` and `since`.
+    ///
+    /// Paths are relative to the repository root.
+    pub fn get_changed_files(&self, since: &str) -> Result<Vec<PathBuf>, error::ResolveError> {
+        let repo = self
+            .git_repo
+            .as_ref()
+            .ok_or(error::ResolveError::GitError {
+                message: "Git repository is not initialized".to_string(),
+            })?;
+        let git_error = |e: git2::Error| error::ResolveError::GitError {
+            message: e.message().to_string(),
+        };
+
+        let since_commit = repo
+            .revparse_single(since)
+            .and_then(|o| o.peel_to_commit())
+            .map_err(git_error)?;
+        let base_oid = match repo.head().and_then(|h| h.peel_to_commit()) {
+            Ok(head) => repo
+                .merge_base(head.id(), since_commit.id())
+                .map_err(git_error)?,
+            Err(_) => since_commit.id(),
+        };
+        let base_tree = repo
+            .find_commit(base_oid)
+            .and_then(|c| c.tree())
+            .map_err(git_error)?;
+
+        let mut diff_opts = git2::DiffOptions::new();
+        diff_opts
+            .include_untracked(true)
+            .recurse_untracked_dirs(true);
+        let diff = repo
+            .diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut diff_opts))
+            .map_err(git_error)?;
+
+        let files = diff
+            .deltas()
+            .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+            .flatten()
+            .map(|p| p.to_path_buf())
+            .collect::<BTreeSet<_>>();
+        Ok(files.into_iter().collect())
+    }
+
+    /// Map changed files to the packages containing them.
+    ///
+    /// A file belongs to the package with the most specific path, and files
+    /// under the changeset root are ignored.
+    pub fn get_changed_packages(&self, files: &[PathBuf]) -> BTreeSet<String> {
+        let normalize = |path: &Path| -> PathBuf {
+            path.components()
+                .filter(|c| !matches!(c, Component::CurDir))
+                .collect()
+        };
+        let changeset_dir = match (&self.changeset_root, &self.repo_root) {
+            (Some(changeset_root), Some(repo_root)) => {
+                pathdiff::diff_paths(changeset_root, repo_root).map(|p| normalize(&p))
+            }
+            _ => None,
+        };
+        let packages = self
+            .get_packages()
+            .into_iter()
+            .map(|(name, cfg)| (name, normalize(&cfg.path)))
+            .collect::<Vec<_>>();
+
+        files
+            .iter()
+            .filter(|file| {
+                !changeset_dir
+                    .as_ref()
+                    .is_some_and(|dir| file.starts_with(dir))
+            })
+            .filter_map(|file| {
+                packages
+                    .iter()
+                    .filter(|(_, path)| file.starts_with(path))
+                    .max_by_key(|(_, path)| path.components().count())
+                    .map(|(name, _)| name.to_string())
+            })
+            .collect()
+    }
+
     pub fn dry_run(&mut self, dry_run: bool) {
         self.dry_run = dry_run
     }
+
+    pub fn snapshot(&mut self, snapshot: Option<String>) {
+        self.snapshot = snapshot
+    }
+
+    pub fn is_snapshot(&self) -> bool {
+        self.snapshot.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn maps_changed_files_to_packages() {
+        let config = test_utils::config(
+            &[
+                ("app", "."),
+                ("core", "./crates/core"),
+                ("core-macros", "crates/core/macros"),
+            ],
+            &[],
+        );
+        let ctx = Context {
+            config: Some(config),
+            changeset_root: Some(PathBuf::from("/repo/.changes")),
+            repo_root: Some(PathBuf::from("/repo")),
+            ..Default::default()
+        };
+
+        let changed = ctx.get_changed_packages(&[
+            PathBuf::from("crates/core/src/lib.rs"),
+            PathBuf::from("crates/core/macros/src/lib.rs"),
+            PathBuf::from(".changes/add-feature.md"),
+        ]);
+        assert_eq!(
+            changed.into_iter().collect::<Vec<_>>(),
+            ["core", "core-macros"]
+        );
+
+        let changed = ctx.get_changed_packages(&[PathBuf::from("README.md")]);
+        assert_eq!(changed.into_iter().collect::<Vec<_>>(), ["app"]);
+    }
 }
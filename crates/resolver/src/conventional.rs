@@ -0,0 +1,278 @@
+use std::{collections::HashMap, path::Path, sync::LazyLock};
+
+use git2::{Oid, Repository};
+use regex::Regex;
+
+use crate::{
+    changeset::{BumpLevel, ChangePackage, Changeset},
+    context::Context,
+    error::ResolveError,
+    utils,
+};
+
+static HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<type>[A-Za-z]+)(?:\((?P<scope>[^()]*)\))?(?P<bang>!)?:\s+(?P<desc>.+)$")
+        .unwrap()
+});
+
+static PR_SUFFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<subject>.*?)\s*\(#(?P<number>\d+)\)$").unwrap());
+
+/// A commit message following the Conventional Commits specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalCommit {
+    /// Type of the change, lowercased, e.g. `feat` or `fix`.
+    pub kind: String,
+    pub scope: Option<String>,
+    pub description: String,
+    /// Breaking change note, from the `BREAKING CHANGE` footer or the
+    /// description when the header is marked with `!`.
+    pub breaking: Option<String>,
+}
+
+impl ConventionalCommit {
+    /// Parse a commit message, returns `None` if it is not conventional.
+    pub fn parse(message: &str) -> Option<Self> {
+        let mut lines = message.lines();
+        let caps = HEADER.captures(lines.next()?.trim())?;
+        let description = caps["desc"].trim().to_string();
+
+        let footer = lines.find_map(|line| {
+            line.strip_prefix("BREAKING CHANGE:")
+                .or_else(|| line.strip_prefix("BREAKING-CHANGE:"))
+                .map(|note| note.trim().to_string())
+        });
+        let breaking = footer.or_else(|| caps.name("bang").map(|_| description.clone()));
+
+        Some(Self {
+            kind: caps["type"].to_lowercase(),
+            scope: caps
+                .name("scope")
+                .map(|s| s.as_str().trim().to_string())
+                .filter(|s| !s.is_empty()),
+            description,
+            breaking,
+        })
+    }
+
+    /// Bump level implied by the commit, breaking changes are major,
+    /// features are minor, fixes and performance improvements are patches.
+    ///
+    /// Other types only note the change in the changelog.
+    pub fn bump_level(&self) -> BumpLevel {
+        if self.breaking.is_some() {
+            return BumpLevel::Major;
+        }
+        match self.kind.as_str() {
+            "feat" => BumpLevel::Minor,
+            "fix" | "perf" => BumpLevel::Patch,
+            _ => BumpLevel::Unchanged,
+        }
+    }
+}
+
+/// Split the pull request reference appended by squash merges, e.g.
+/// `Add feature (#12)`.
+pub fn split_pr_suffix(subject: &str) -> (&str, Option<u64>) {
+    match PR_SUFFIX.captures(subject) {
+        Some(caps) => (
+            caps.name("subject").unwrap().as_str(),
+            caps["number"].parse().ok(),
+        ),
+        None => (subject, None),
+    }
+}
+
+/// Build an in-memory changeset of a commit touching a package.
+///
+/// A conventional commit is classified into the configured tags by its type,
+/// other commits are summarized by their subject line as patch changes.
+pub fn commit_changeset(
+    ctx: &Context,
+    name: String,
+    root: &Path,
+    package_name: &str,
+    oid: Oid,
+    message: &str,
+    parsed: Option<&ConventionalCommit>,
+) -> Changeset {
+    let subject = message.lines().next().unwrap_or_default().trim();
+    let description = parsed.map_or(subject, |c| c.description.as_str());
+    let (summary, pr) = split_pr_suffix(description);
+    let tag = parsed.map(|c| c.kind.clone()).filter(|kind| {
+        ctx.config
+            .as_ref()
+            .is_some_and(|config| config.tags.contains_key(kind))
+    });
+
+    let mut changeset = Changeset::new(name, root);
+    changeset.packages.push(ChangePackage {
+        name: package_name.to_string(),
+        level: parsed.map_or(BumpLevel::Patch, ConventionalCommit::bump_level),
+        tag,
+        version: None,
+    });
+    changeset.summary = summary.to_string();
+    changeset.pr = pr;
+    changeset.breaking = parsed.and_then(|c| c.breaking.clone());
+    changeset.commit = Some(oid.to_string());
+    changeset
+}
+
+/// Derive changesets from the conventional commits touching each package
+/// since its last release tag.
+///
+/// A commit touching several packages becomes a single changeset, commits
+/// which are not conventional are ignored.
+pub fn commit_changesets(ctx: &Context, repo: &Repository) -> Result<Vec<Changeset>, ResolveError> {
+    let git_error = |e: git2::Error| ResolveError::GitError {
+        message: e.message().to_string(),
+    };
+    let root = ctx.changeset_root.clone().unwrap_or_default();
+    let head = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .map_err(git_error)?
+        .id();
+
+    let mut changesets: Vec<Changeset> = Vec::new();
+    let mut indices: HashMap<Oid, usize> = HashMap::new();
+    for (package_name, package_config) in ctx.get_packages() {
+        let since = utils::find_last_release_tag(repo, [package_name.as_str()]);
+        let commits =
+            utils::commits_touching(repo, since, head, &package_config.path).map_err(git_error)?;
+        log::debug!(
+            "{} commits touching {} since {:?}",
+            commits.len(),
+            package_name,
+            since
+        );
+
+        for oid in commits {
+            let commit = repo.find_commit(oid).map_err(git_error)?;
+            let Some(parsed) = ConventionalCommit::parse(commit.message().unwrap_or_default())
+            else {
+                continue;
+            };
+            if let Some(&idx) = indices.get(&oid) {
+                let changeset = &mut changesets[idx];
+                let mut package = changeset.packages[0].clone();
+                package.name = package_name.clone();
+                changeset.packages.push(package);
+                continue;
+            }
+            let short_id = oid.to_string()[..7].to_string();
+            indices.insert(oid, changesets.len());
+            changesets.push(commit_changeset(
+                ctx,
+                format!("commit-{short_id}"),
+                &root,
+                package_name,
+                oid,
+                commit.message().unwrap_or_default(),
+                Some(&parsed),
+            ));
+        }
+    }
+
+    Ok(changesets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, commit, temp_repo};
+
+    #[test]
+    fn parses_conventional_commits() {
+        let commit = ConventionalCommit::parse("feat(cli): add backfill (#12)\n\nBody").unwrap();
+        assert_eq!(commit.kind, "feat");
+        assert_eq!(commit.scope.as_deref(), Some("cli"));
+        assert_eq!(commit.description, "add backfill (#12)");
+        assert_eq!(commit.breaking, None);
+        assert_eq!(commit.bump_level(), BumpLevel::Minor);
+
+        let commit = ConventionalCommit::parse("fix!: drop old config").unwrap();
+        assert_eq!(commit.breaking.as_deref(), Some("drop old config"));
+        assert_eq!(commit.bump_level(), BumpLevel::Major);
+
+        let commit =
+            ConventionalCommit::parse("refactor: rename\n\nBREAKING CHANGE: `foo` is now `bar`")
+                .unwrap();
+        assert_eq!(commit.breaking.as_deref(), Some("`foo` is now `bar`"));
+
+        let commit = ConventionalCommit::parse("docs: update readme").unwrap();
+        assert_eq!(commit.bump_level(), BumpLevel::Unchanged);
+
+        assert_eq!(ConventionalCommit::parse("Update README"), None);
+        assert_eq!(ConventionalCommit::parse("Merge branch 'main'"), None);
+    }
+
+    #[test]
+    fn splits_pr_suffix() {
+        assert_eq!(split_pr_suffix("Add thing (#42)"), ("Add thing", Some(42)));
+        assert_eq!(split_pr_suffix("Add thing"), ("Add thing", None));
+    }
+
+    #[test]
+    fn derives_changesets_from_commits_since_last_tag() {
+        let (dir, repo) = temp_repo("commit-changesets");
+
+        commit(&repo, &[("a/lib.rs", Some("fix"))], "fix: released fix");
+        let released = repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap();
+        repo.tag_lightweight(&utils::release_tag_name("a", "1.0.0"), &released, false)
+            .unwrap();
+        let shared = commit(
+            &repo,
+            &[("a/lib.rs", Some("feat")), ("b/lib.rs", Some("feat"))],
+            "feat: shared feature (#5)",
+        );
+        commit(&repo, &[("a/lib.rs", Some("update"))], "Update a");
+        let docs = commit(&repo, &[("b/lib.rs", Some("docs"))], "docs: document b");
+
+        let config = test_utils::config(&[("a", "a"), ("b", "b")], &[("feat", "New Features")]);
+        let ctx = Context {
+            config: Some(config),
+            ..Default::default()
+        };
+        let changesets = commit_changesets(&ctx, &repo).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let summary = changesets
+            .iter()
+            .map(|c| {
+                let packages = c
+                    .packages
+                    .iter()
+                    .map(|p| (p.name.as_str(), p.level, p.tag.as_deref()))
+                    .collect::<Vec<_>>();
+                (
+                    c.commit.clone().unwrap(),
+                    c.summary.as_str(),
+                    c.pr,
+                    packages,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (
+                    shared.to_string(),
+                    "shared feature",
+                    Some(5),
+                    vec![
+                        ("a", BumpLevel::Minor, Some("feat")),
+                        ("b", BumpLevel::Minor, Some("feat")),
+                    ],
+                ),
+                (
+                    docs.to_string(),
+                    "document b",
+                    None,
+                    vec![("b", BumpLevel::Unchanged, None)],
+                ),
+            ]
+        );
+    }
+}
@@ -28,6 +28,8 @@ pub enum ResolveError {
     GitHubError { message: String },
     #[error("Pre-release tag {tag} is invalid: {message}")]
     PreReleaseTagInvalid { tag: String, message: String },
+    #[error("Invalid template {name}: {reason}")]
+    TemplateError { name: String, reason: String },
     #[error("Semver error: {0}")]
     SemverError(#[from] semver::Error),
 }
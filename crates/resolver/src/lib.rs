@@ -1,6 +1,12 @@
 pub mod changeset;
 pub mod config;
 pub mod context;
+pub mod conventional;
 pub mod error;
+pub mod plan;
+pub mod pre;
 pub mod resolver;
+#[cfg(test)]
+mod test_utils;
+pub mod transaction;
 pub mod utils;
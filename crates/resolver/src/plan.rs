@@ -0,0 +1,610 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    changeset::{BumpLevel, Changeset},
+    config::{PackageConfig, VersionMode},
+    context::Context,
+    error::ResolveError,
+    pre::PRE_STATE_FILE,
+    utils,
+};
+
+/// Version of the machine-readable output schema, bumped on breaking changes
+/// of the types in this module.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Pending changeset of a plan.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlannedChangeset {
+    pub name: String,
+    /// Content hash of the changeset, so that edits after planning are
+    /// detected when the plan is applied.
+    pub hash: String,
+}
+
+impl PlannedChangeset {
+    pub fn new(changeset: &Changeset) -> Result<Self, ResolveError> {
+        Ok(Self {
+            name: changeset.name.clone(),
+            hash: changeset.content_hash()?,
+        })
+    }
+}
+
+/// Planned release of a package.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PackageRelease {
+    pub name: String,
+    pub level: BumpLevel,
+    pub current_version: semver::Version,
+    pub next_version: semver::Version,
+    /// Internal dependencies released by the same plan, whose requirements
+    /// are updated with the package.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+    /// Released dependencies which caused the release, only set for packages
+    /// without pending changes of their own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bumped_by: Vec<String>,
+    /// Files written by the release, relative to the repository root.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// Changelog entry of the release, only set once it is generated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<String>,
+}
+
+/// Problem found while planning, which doesn't prevent the release.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum PlanWarning {
+    /// A pre-release is bumped outside of pre-release mode, which releases a
+    /// stable version.
+    PreRelease { package: String },
+    /// An explicit version is not greater than the current version.
+    ExplicitNotGreater {
+        package: String,
+        version: semver::Version,
+        current: semver::Version,
+    },
+}
+
+/// Releases computed from the pending changesets.
+///
+/// Serialized as the JSON output of `status` and `version`, and as the plan
+/// file of `version --plan-out` and `--plan-in`:
+///
+/// ```json
+/// {
+///   "schema_version": 2,
+///   "dry_run": false,
+///   "changesets": [{ "name": "add-feature", "hash": "3b18e512dba79e4c8300dd08aeb37f8e728b8dad" }],
+///   "releases": [
+///     {
+///       "name": "semifold",
+///       "level": "minor",
+///       "current_version": "1.0.0",
+///       "next_version": "1.1.0",
+///       "dependencies": ["semifold-resolver"],
+///       "files": ["crates/semifold/Cargo.toml", "crates/semifold/CHANGELOG.md"],
+///       "changelog": "## v1.1.0\n\n..."
+///     },
+///     {
+///       "name": "semifold-cli",
+///       "level": "patch",
+///       "current_version": "1.0.0",
+///       "next_version": "1.0.1",
+///       "dependencies": ["semifold"],
+///       "bumped_by": ["semifold"],
+///       "files": ["crates/semifold-cli/Cargo.toml", "crates/semifold-cli/CHANGELOG.md"]
+///     }
+///   ],
+///   "notes": [],
+///   "files": [".changes/add-feature.md"],
+///   "warnings": [{ "kind": "pre-release", "package": "semifold" }],
+///   "missing_changesets": []
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReleasePlan {
+    pub schema_version: u32,
+    pub dry_run: bool,
+    /// Name of the snapshot release, if the plan is a snapshot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    /// Pending changesets.
+    pub changesets: Vec<PlannedChangeset>,
+    /// Packages to release, in release order.
+    pub releases: Vec<PackageRelease>,
+    /// Packages with pending notes but without a version bump.
+    pub notes: Vec<String>,
+    /// Files written besides the ones of the releases, relative to the
+    /// repository root, e.g. the root changelog and consumed changesets.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    pub warnings: Vec<PlanWarning>,
+    /// Changed packages which are not mentioned in any changeset.
+    #[serde(default)]
+    pub missing_changesets: Vec<String>,
+    /// Unified diff of the writes of a dry run, only set in the JSON output
+    /// of `version --dry-run` without `--patch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+impl ReleasePlan {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            dry_run,
+            snapshot: None,
+            changesets: Vec::new(),
+            releases: Vec::new(),
+            notes: Vec::new(),
+            files: Vec::new(),
+            warnings: Vec::new(),
+            missing_changesets: Vec::new(),
+            diff: None,
+        }
+    }
+
+    pub fn get_release(&self, name: &str) -> Option<&PackageRelease> {
+        self.releases.iter().find(|r| r.name == name)
+    }
+}
+
+/// Outcome of a package in a publish run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PublishedPackage {
+    pub name: String,
+    pub version: semver::Version,
+    /// Why the package was skipped or failed, e.g. `already-published` or
+    /// `private` for skipped packages and the error for failed ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Serialized as the JSON output of `publish`:
+///
+/// ```json
+/// {
+///   "schema_version": 2,
+///   "dry_run": false,
+///   "published": [{ "name": "semifold", "version": "1.1.0" }],
+///   "skipped": [{ "name": "web", "version": "2.0.0", "reason": "private" }],
+///   "failed": []
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PublishReport {
+    pub schema_version: u32,
+    pub dry_run: bool,
+    pub published: Vec<PublishedPackage>,
+    pub skipped: Vec<PublishedPackage>,
+    /// Packages which failed to publish, the run stops at the first failure.
+    pub failed: Vec<PublishedPackage>,
+}
+
+impl PublishReport {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            dry_run,
+            published: Vec::new(),
+            skipped: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+}
+
+/// Reason of packages skipped because their version is already published.
+pub const SKIP_ALREADY_PUBLISHED: &str = "already-published";
+/// Reason of private packages, which are never published.
+pub const SKIP_PRIVATE: &str = "private";
+
+/// Get changesets which are not yet released by a previous pre-release.
+///
+/// Released changesets are kept only for the notes of packages which are not
+/// released yet.
+pub fn pending_changesets(ctx: &Context, changesets: &[Changeset]) -> Vec<Changeset> {
+    match &ctx.pre_state {
+        Some(pre_state) => changesets
+            .iter()
+            .filter_map(|c| pre_state.unreleased(c))
+            .collect(),
+        None => changesets.to_vec(),
+    }
+}
+
+/// Compute the bump level and the next version of a package.
+///
+/// Returns `None` if the package has no pending changes.
+pub fn next_version(
+    ctx: &Context,
+    package_name: &str,
+    package_config: &PackageConfig,
+    current: &semver::Version,
+    changesets: &[Changeset],
+) -> Result<Option<(BumpLevel, semver::Version)>, ResolveError> {
+    let level = utils::get_bump_level(&pending_changesets(ctx, changesets), package_name);
+    if matches!(level, BumpLevel::Unchanged) {
+        return Ok(None);
+    }
+
+    let explicit = utils::get_explicit_version(changesets, package_name)?;
+    let bumped_version = if let Some(pre_state) = &ctx.pre_state {
+        let total_level = utils::get_bump_level(changesets, package_name);
+        pre_state.bump_version(package_name, current, total_level, explicit.as_ref())?
+    } else {
+        let mut bumped_version = explicit.unwrap_or_else(|| current.clone());
+        utils::bump_version(&mut bumped_version, level, &package_config.version_mode)?;
+        bumped_version
+    };
+    Ok(Some((level, bumped_version)))
+}
+
+/// Bump level of packages released only because their dependencies are.
+pub const DEPENDENCY_BUMP_LEVEL: BumpLevel = BumpLevel::Patch;
+
+/// Compute the next version of a package released only because its
+/// dependencies are released.
+pub fn dependency_version(
+    ctx: &Context,
+    package_name: &str,
+    package_config: &PackageConfig,
+    current: &semver::Version,
+    changesets: &[Changeset],
+) -> Result<semver::Version, ResolveError> {
+    if let Some(pre_state) = &ctx.pre_state {
+        let total_level =
+            utils::get_bump_level(changesets, package_name).max(DEPENDENCY_BUMP_LEVEL);
+        return pre_state.bump_version(package_name, current, total_level, None);
+    }
+    let mut bumped_version = current.clone();
+    utils::bump_version(
+        &mut bumped_version,
+        DEPENDENCY_BUMP_LEVEL,
+        &package_config.version_mode,
+    )?;
+    Ok(bumped_version)
+}
+
+/// Compute the snapshot version of a package from its next version.
+///
+/// `time` is the start of the run, shared by all packages of a snapshot.
+pub fn snapshot_version(
+    ctx: &Context,
+    name: &str,
+    next: &semver::Version,
+    time: &chrono::DateTime<chrono::Utc>,
+) -> Result<semver::Version, ResolveError> {
+    let config = ctx.config.as_ref().unwrap();
+    let version = if config.snapshot.use_calculated_version {
+        let sha = ctx
+            .git_repo
+            .as_ref()
+            .ok_or(ResolveError::GitError {
+                message: "Git repository is not initialized".to_string(),
+            })?
+            .head()
+            .and_then(|h| h.peel_to_commit())
+            .map_err(|e| ResolveError::GitError {
+                message: e.message().to_string(),
+            })?
+            .id()
+            .to_string();
+        let mut version = semver::Version::new(next.major, next.minor, next.patch);
+        version.pre = semver::Prerelease::new(&format!("{name}.{}", &sha[..7]))?;
+        version
+    } else {
+        let timestamp = time.format("%Y%m%d%H%M%S");
+        let mut version = semver::Version::new(0, 0, 0);
+        version.pre = semver::Prerelease::new(&format!("{name}-{timestamp}"))?;
+        version
+    };
+    Ok(version)
+}
+
+/// Plan the releases of the pending changesets, in release order.
+///
+/// Nothing is written, the plan is applied by `semifold version`. Packages
+/// without changes of their own are released too when one of their internal
+/// dependencies is, with a [`DEPENDENCY_BUMP_LEVEL`] bump.
+pub fn create_plan(ctx: &Context, changesets: &[Changeset]) -> Result<ReleasePlan, ResolveError> {
+    let config = ctx.config.as_ref().unwrap();
+    let root = ctx.repo_root.clone().unwrap_or(std::env::current_dir()?);
+    let pending = pending_changesets(ctx, changesets);
+
+    let mut plan = ReleasePlan::new(ctx.dry_run);
+    plan.snapshot = ctx.snapshot.clone();
+    plan.changesets = pending
+        .iter()
+        .map(PlannedChangeset::new)
+        .collect::<Result<_, _>>()?;
+    let now = chrono::Utc::now();
+
+    let mut sorted_packages = config.packages.clone().into_iter().collect::<Vec<_>>();
+    for resolver in config.resolver.keys() {
+        ctx.create_resolver(*resolver)
+            .sort_packages(&root, &mut sorted_packages)?;
+    }
+    for (package_name, package_config) in &sorted_packages {
+        let mut resolver = ctx.create_resolver(package_config.resolver);
+        let resolved_package = resolver.resolve(&root, package_config)?;
+        let current = &resolved_package.version;
+        // Packages are sorted by their dependencies, which are planned first
+        let mut dependencies = resolver.dependencies(&root, package_config)?;
+        dependencies.retain(|name| name != package_name && plan.get_release(name).is_some());
+        dependencies.sort();
+        dependencies.dedup();
+        let mut bumped_by = Vec::new();
+        let (level, next) =
+            match next_version(ctx, package_name, package_config, current, changesets)? {
+                Some(bump) => bump,
+                None if !dependencies.is_empty() => {
+                    bumped_by = dependencies.clone();
+                    let next =
+                        dependency_version(ctx, package_name, package_config, current, changesets)?;
+                    (DEPENDENCY_BUMP_LEVEL, next)
+                }
+                None => {
+                    if pending
+                        .iter()
+                        .any(|c| c.packages.iter().any(|p| &p.name == package_name))
+                    {
+                        plan.notes.push(package_name.clone());
+                    }
+                    continue;
+                }
+            };
+
+        if level == BumpLevel::Exact && next <= *current {
+            plan.warnings.push(PlanWarning::ExplicitNotGreater {
+                package: package_name.clone(),
+                version: next.clone(),
+                current: current.clone(),
+            });
+        }
+        if ctx.pre_state.is_none()
+            && matches!(package_config.version_mode, VersionMode::Semantic)
+            && level != BumpLevel::Exact
+            && level > utils::prerelease_level(current)
+            && !current.pre.is_empty()
+        {
+            plan.warnings.push(PlanWarning::PreRelease {
+                package: package_name.clone(),
+            });
+        }
+
+        let next = match &ctx.snapshot {
+            Some(name) => snapshot_version(ctx, name, &next, &now)?,
+            None => next,
+        };
+        // Files are found by bumping into the transaction, discarding the writes
+        let (bumped, files) = ctx
+            .transaction
+            .scratch(|| resolver.bump(ctx, &root, &resolved_package, &next));
+        bumped?;
+        let mut files = files
+            .into_iter()
+            .map(|path| {
+                path.strip_prefix(&root)
+                    .map_or(path.clone(), Path::to_path_buf)
+            })
+            .collect::<Vec<_>>();
+        // Snapshot releases never write changelogs
+        if !ctx.is_snapshot() {
+            files.push(package_config.path.join("CHANGELOG.md"));
+        }
+        plan.releases.push(PackageRelease {
+            name: package_name.clone(),
+            level,
+            current_version: current.clone(),
+            next_version: next,
+            dependencies,
+            bumped_by,
+            files,
+            changelog: None,
+        });
+    }
+
+    if !ctx.is_snapshot() && !plan.releases.is_empty() {
+        if let Some(root_changelog) = &config.changelog.root {
+            plan.files.push(root_changelog.clone());
+        }
+        let changeset_root = ctx.changeset_root.as_ref().unwrap_or(&root);
+        if ctx.pre_state.is_some() {
+            plan.files.push(
+                changeset_root
+                    .strip_prefix(&root)
+                    .unwrap_or(changeset_root)
+                    .join(PRE_STATE_FILE),
+            );
+        }
+        // Changesets are kept in pre-release mode until it is exited
+        if !ctx.pre_state.as_ref().is_some_and(|s| s.is_pre()) {
+            plan.files.extend(pending.iter().filter_map(|c| {
+                let path = c.path.as_ref()?;
+                Some(path.strip_prefix(&root).unwrap_or(path).to_path_buf())
+            }));
+        }
+    }
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::Path};
+
+    use super::*;
+    use crate::{
+        pre::PreState,
+        test_utils::{self, temp_dir},
+    };
+
+    fn test_context() -> Context {
+        Context {
+            config: Some(test_utils::config(&[("a", "a"), ("b", "b")], &[])),
+            ..Default::default()
+        }
+    }
+
+    fn next(ctx: &Context, name: &str, current: &str, changesets: &[Changeset]) -> Option<String> {
+        let config = ctx.get_package_config(name).unwrap();
+        let current = semver::Version::parse(current).unwrap();
+        next_version(ctx, name, config, &current, changesets)
+            .unwrap()
+            .map(|(_, version)| version.to_string())
+    }
+
+    #[test]
+    fn keeps_notes_of_released_changesets_across_pre_releases() {
+        let mut ctx = test_context();
+        let initial_versions = BTreeMap::from([
+            ("a".to_string(), semver::Version::new(1, 0, 0)),
+            ("b".to_string(), semver::Version::new(1, 0, 0)),
+        ]);
+        ctx.pre_state = Some(PreState::new("beta".to_string(), initial_versions));
+        let mut first = Changeset::new("first".to_string(), Path::new(".changes"));
+        first.add_package("a".to_string(), BumpLevel::Minor, None);
+        first.add_package("b".to_string(), BumpLevel::Unchanged, None);
+        let mut changesets = vec![first];
+
+        assert_eq!(
+            next(&ctx, "a", "1.0.0", &changesets).as_deref(),
+            Some("1.1.0-beta.0")
+        );
+        assert_eq!(next(&ctx, "b", "1.0.0", &changesets), None);
+        let pending = pending_changesets(&ctx, &changesets);
+        let pre_state = ctx.pre_state.as_mut().unwrap();
+        pre_state.record_released(&pending, |name| name == "a");
+        assert_eq!(pre_state.changesets, ["first"]);
+
+        let mut second = Changeset::new("second".to_string(), Path::new(".changes"));
+        second.add_package("b".to_string(), BumpLevel::Patch, None);
+        changesets.push(second);
+
+        // Only the note of `b` is left from the released changeset
+        let pending = pending_changesets(&ctx, &changesets);
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].name, "first");
+        assert_eq!(pending[0].packages.len(), 1);
+        assert_eq!(pending[0].packages[0].name, "b");
+        assert_eq!(next(&ctx, "a", "1.1.0-beta.0", &changesets), None);
+        assert_eq!(
+            next(&ctx, "b", "1.0.0", &changesets).as_deref(),
+            Some("1.0.1-beta.0")
+        );
+
+        let pre_state = ctx.pre_state.as_mut().unwrap();
+        pre_state.record_released(&pending, |name| name == "b");
+        assert_eq!(pre_state.changesets, ["first", "second"]);
+        assert!(pre_state.notes.is_empty());
+        assert!(pending_changesets(&ctx, &changesets).is_empty());
+    }
+
+    #[test]
+    fn plans_patch_releases_of_dependents() {
+        let root = temp_dir("plan-deps");
+        for (name, version, dependencies) in [
+            ("a", "1.0.0", ""),
+            (
+                "b",
+                "0.3.0",
+                "a = { version = \"1.0.0\", path = \"../a\" }\n",
+            ),
+        ] {
+            std::fs::create_dir_all(root.join(name)).unwrap();
+            std::fs::write(
+                root.join(name).join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"{name}\"\nversion = \"{version}\"\n\n[dependencies]\n{dependencies}"
+                ),
+            )
+            .unwrap();
+        }
+        let mut ctx = test_context();
+        ctx.repo_root = Some(root.clone());
+        let mut changeset = Changeset::new("feature".to_string(), Path::new(".changes"));
+        changeset.add_package("a".to_string(), BumpLevel::Minor, None);
+
+        let plan = create_plan(&ctx, &[changeset]).unwrap();
+        let a = plan.get_release("a").unwrap();
+        assert_eq!(a.next_version.to_string(), "1.1.0");
+        assert!(a.bumped_by.is_empty());
+        let b = plan.get_release("b").unwrap();
+        assert_eq!(b.level, BumpLevel::Patch);
+        assert_eq!(b.next_version.to_string(), "0.3.1");
+        assert_eq!(b.dependencies, ["a"]);
+        assert_eq!(b.bumped_by, ["a"]);
+        assert_eq!(
+            b.files,
+            [
+                PathBuf::from("b/Cargo.toml"),
+                PathBuf::from("b/CHANGELOG.md")
+            ]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn formats_snapshot_version_from_run_time() {
+        let ctx = test_context();
+        let time = chrono::DateTime::parse_from_rfc3339("2025-03-04T05:06:07Z")
+            .unwrap()
+            .to_utc();
+        let next = semver::Version::new(1, 2, 0);
+        let version = snapshot_version(&ctx, "nightly", &next, &time).unwrap();
+        assert_eq!(version.to_string(), "0.0.0-nightly-20250304050607");
+    }
+
+    #[test]
+    fn serializes_stable_schema() {
+        let mut plan = ReleasePlan::new(true);
+        plan.changesets.push(PlannedChangeset {
+            name: "add-feature".to_string(),
+            hash: "3b18e512dba79e4c8300dd08aeb37f8e728b8dad".to_string(),
+        });
+        plan.releases.push(PackageRelease {
+            name: "a".to_string(),
+            level: BumpLevel::Minor,
+            current_version: semver::Version::new(1, 0, 0),
+            next_version: semver::Version::new(1, 1, 0),
+            dependencies: Vec::new(),
+            bumped_by: Vec::new(),
+            files: vec![PathBuf::from("a/Cargo.toml")],
+            changelog: None,
+        });
+        plan.warnings.push(PlanWarning::PreRelease {
+            package: "a".to_string(),
+        });
+
+        let value = serde_json::to_value(&plan).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "schema_version": 2,
+                "dry_run": true,
+                "changesets": [{
+                    "name": "add-feature",
+                    "hash": "3b18e512dba79e4c8300dd08aeb37f8e728b8dad",
+                }],
+                "releases": [{
+                    "name": "a",
+                    "level": "minor",
+                    "current_version": "1.0.0",
+                    "next_version": "1.1.0",
+                    "files": ["a/Cargo.toml"],
+                }],
+                "notes": [],
+                "files": [],
+                "warnings": [{ "kind": "pre-release", "package": "a" }],
+                "missing_changesets": [],
+            })
+        );
+        assert_eq!(serde_json::from_value::<ReleasePlan>(value).unwrap(), plan);
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    changeset::{BumpLevel, Changeset},
+    config::VersionMode,
+    error::ResolveError,
+    transaction::Transaction,
+    utils,
+};
+
+/// Name of the pre-release state file in the changeset root.
+pub const PRE_STATE_FILE: &str = "pre.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PreMode {
+    /// Changesets are released as pre-releases and kept after versioning.
+    Pre,
+    /// Next versioning releases stable versions and consumes all changesets.
+    Exit,
+}
+
+/// Persistent state of the pre-release mode.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreState {
+    /// Current mode.
+    pub mode: PreMode,
+    /// Pre-release tag, e.g. `beta`.
+    pub tag: String,
+    /// Versions of packages when entering pre-release mode.
+    #[serde(rename = "initial-versions")]
+    pub initial_versions: BTreeMap<String, semver::Version>,
+    /// Names of changesets already released as pre-releases.
+    #[serde(default)]
+    pub changesets: Vec<String>,
+    /// Packages noted by released changesets which are not released yet, by
+    /// changeset name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub notes: BTreeMap<String, Vec<String>>,
+}
+
+impl PreState {
+    pub fn new(tag: String, initial_versions: BTreeMap<String, semver::Version>) -> Self {
+        Self {
+            mode: PreMode::Pre,
+            tag,
+            initial_versions,
+            changesets: Vec::new(),
+            notes: BTreeMap::new(),
+        }
+    }
+
+    pub fn path(changeset_root: &Path) -> PathBuf {
+        changeset_root.join(PRE_STATE_FILE)
+    }
+
+    pub fn load(changeset_root: &Path) -> Result<Option<Self>, ResolveError> {
+        let path = Self::path(changeset_root);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let state = serde_json::from_str(&content).map_err(|e| ResolveError::ParseError {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
+        Ok(Some(state))
+    }
+
+    fn render(&self, path: &Path) -> Result<String, ResolveError> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| ResolveError::ParseError {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        Ok(content + "\n")
+    }
+
+    pub fn save(&self, changeset_root: &Path) -> Result<(), ResolveError> {
+        let path = Self::path(changeset_root);
+        std::fs::write(&path, self.render(&path)?)?;
+        Ok(())
+    }
+
+    /// Stage the state file into a transaction, like [`PreState::save`].
+    pub fn save_in(
+        &self,
+        transaction: &Transaction,
+        changeset_root: &Path,
+    ) -> Result<(), ResolveError> {
+        let path = Self::path(changeset_root);
+        transaction.write(&path, self.render(&path)?);
+        Ok(())
+    }
+
+    pub fn remove(changeset_root: &Path) -> Result<(), ResolveError> {
+        let path = Self::path(changeset_root);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Stage the removal of the state file into a transaction.
+    pub fn remove_in(transaction: &Transaction, changeset_root: &Path) {
+        let path = Self::path(changeset_root);
+        if transaction.exists(&path) {
+            transaction.remove(path);
+        }
+    }
+
+    pub fn is_pre(&self) -> bool {
+        self.mode == PreMode::Pre
+    }
+
+    /// Whether the changeset has been released in a previous pre-release.
+    pub fn is_consumed(&self, changeset: &Changeset) -> bool {
+        self.is_pre() && self.changesets.contains(&changeset.name)
+    }
+
+    /// Part of a changeset which is not released by a previous pre-release.
+    ///
+    /// Released changesets only keep the notes of packages which are not
+    /// released yet, so that they are released with the next version of them.
+    pub fn unreleased(&self, changeset: &Changeset) -> Option<Changeset> {
+        if !self.is_consumed(changeset) {
+            return Some(changeset.clone());
+        }
+        let notes = self.notes.get(&changeset.name)?;
+        let mut changeset = changeset.clone();
+        changeset.packages.retain(|p| notes.contains(&p.name));
+        (!changeset.packages.is_empty()).then_some(changeset)
+    }
+
+    /// Record the changesets released by a pre-release.
+    ///
+    /// A changeset is released once any of its packages is, the notes of its
+    /// other packages are kept until they are released too.
+    pub fn record_released<F: Fn(&str) -> bool>(
+        &mut self,
+        changesets: &[Changeset],
+        is_released: F,
+    ) {
+        for changeset in changesets {
+            if !changeset.packages.iter().any(|p| is_released(&p.name)) {
+                continue;
+            }
+            if !self.changesets.contains(&changeset.name) {
+                self.changesets.push(changeset.name.clone());
+            }
+            let notes = changeset
+                .packages
+                .iter()
+                .filter(|p| !is_released(&p.name))
+                .map(|p| p.name.clone())
+                .collect::<Vec<_>>();
+            if notes.is_empty() {
+                self.notes.remove(&changeset.name);
+            } else {
+                self.notes.insert(changeset.name.clone(), notes);
+            }
+        }
+    }
+
+    /// Compute the next version of a package from its initial version.
+    ///
+    /// `level` is the highest bump level over all changesets of the package,
+    /// including the ones already consumed by previous pre-releases, and
+    /// `explicit` is the explicit version set by them.
+    pub fn bump_version(
+        &self,
+        package_name: &str,
+        current: &semver::Version,
+        level: BumpLevel,
+        explicit: Option<&semver::Version>,
+    ) -> Result<semver::Version, ResolveError> {
+        let mut target = explicit
+            .or(self.initial_versions.get(package_name))
+            .unwrap_or(current)
+            .clone();
+        utils::bump_version(&mut target, level, &VersionMode::Semantic)?;
+
+        if !self.is_pre() {
+            return Ok(target);
+        }
+
+        let same_release = (current.major, current.minor, current.patch)
+            == (target.major, target.minor, target.patch);
+        if same_release && !current.pre.is_empty() {
+            let mut version = current.clone();
+            utils::bump_prerelease(&mut version, &self.tag)?;
+            Ok(version)
+        } else {
+            target.pre = semver::Prerelease::new(&format!("{}.0", self.tag))?;
+            Ok(target)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(mode: PreMode) -> PreState {
+        let mut state = PreState::new(
+            "beta".to_string(),
+            BTreeMap::from([("a".to_string(), semver::Version::new(1, 0, 0))]),
+        );
+        state.mode = mode;
+        state
+    }
+
+    fn bump(state: &PreState, current: &str, level: BumpLevel, explicit: Option<&str>) -> String {
+        let current = semver::Version::parse(current).unwrap();
+        let explicit = explicit.map(|v| semver::Version::parse(v).unwrap());
+        state
+            .bump_version("a", &current, level, explicit.as_ref())
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn bumps_pre_releases_from_initial_version() {
+        let pre = state(PreMode::Pre);
+        assert_eq!(bump(&pre, "1.0.0", BumpLevel::Minor, None), "1.1.0-beta.0");
+        assert_eq!(
+            bump(&pre, "1.1.0-beta.0", BumpLevel::Minor, None),
+            "1.1.0-beta.1"
+        );
+        assert_eq!(
+            bump(&pre, "1.1.0-beta.1", BumpLevel::Minor, None),
+            "1.1.0-beta.2"
+        );
+        assert_eq!(
+            bump(&pre, "1.1.0-beta.2", BumpLevel::Major, None),
+            "2.0.0-beta.0"
+        );
+        assert_eq!(
+            bump(&pre, "1.0.0", BumpLevel::Exact, Some("3.0.0")),
+            "3.0.0-beta.0"
+        );
+    }
+
+    #[test]
+    fn releases_stable_version_on_exit() {
+        let exit = state(PreMode::Exit);
+        assert_eq!(bump(&exit, "1.1.0-beta.2", BumpLevel::Minor, None), "1.1.0");
+        assert_eq!(bump(&exit, "2.0.0-beta.0", BumpLevel::Major, None), "2.0.0");
+    }
+}
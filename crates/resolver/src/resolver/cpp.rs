@@ -7,6 +7,7 @@ use crate::{
     context,
     error::ResolveError,
     resolver::{ResolvedPackage, Resolver, ResolverType},
+    transaction::Transaction,
     utils,
 };
 
@@ -70,11 +71,12 @@ impl CppResolver {
     /// Update version in CMakeLists.txt
     fn update_cmake_version(
         &self,
+        transaction: &Transaction,
         package_path: &Path,
         new_version: &str,
     ) -> Result<(), ResolveError> {
         let cmake_path = package_path.join("CMakeLists.txt");
-        let content = std::fs::read_to_string(&cmake_path)?;
+        let content = transaction.read_to_string(&cmake_path)?;
 
         // Replace version in project() declaration
         let re = Regex::new(
@@ -89,7 +91,7 @@ impl CppResolver {
             format!("{}{}", &caps[1], new_version)
         });
 
-        std::fs::write(&cmake_path, updated_content.as_ref())?;
+        transaction.write(&cmake_path, updated_content);
         log::info!("Updated {:?} to version {}", cmake_path, new_version);
         Ok(())
     }
@@ -97,17 +99,18 @@ impl CppResolver {
     /// Update version in vcpkg.json if it exists (optional)
     fn update_vcpkg_version(
         &self,
+        transaction: &Transaction,
         package_path: &Path,
         new_version: &str,
     ) -> Result<(), ResolveError> {
         let vcpkg_path = package_path.join("vcpkg.json");
 
-        if !vcpkg_path.exists() {
+        if !transaction.exists(&vcpkg_path) {
             log::debug!("Skipping optional file {:?} (not found)", vcpkg_path);
             return Ok(());
         }
 
-        let content = std::fs::read_to_string(&vcpkg_path)?;
+        let content = transaction.read_to_string(&vcpkg_path)?;
         let mut vcpkg_json: serde_json::Value =
             serde_json::from_str(&content).map_err(|e| ResolveError::ParseError {
                 path: vcpkg_path.clone(),
@@ -132,7 +135,7 @@ impl CppResolver {
                 reason: e.to_string(),
             })?;
 
-        std::fs::write(&vcpkg_path, updated_content)?;
+        transaction.write(&vcpkg_path, updated_content);
         log::info!("Updated {:?} to version {}", vcpkg_path, new_version);
         Ok(())
     }
@@ -185,6 +188,7 @@ impl Resolver for CppResolver {
                 resolver: ResolverType::Cpp,
                 version_mode: VersionMode::Semantic,
                 assets: vec![],
+                contributors: None,
             },
         )?;
 
@@ -201,24 +205,43 @@ impl Resolver for CppResolver {
         let bumped_version = version.to_string();
         let package_path = root.join(&package.path);
 
-        if ctx.dry_run {
-            log::warn!(
-                "Skip bump for {} to version {} due to dry run",
-                package.name,
-                bumped_version
-            );
-            return Ok(());
-        }
-
         // Update CMakeLists.txt (required)
-        self.update_cmake_version(&package_path, &bumped_version)?;
+        self.update_cmake_version(&ctx.transaction, &package_path, &bumped_version)?;
 
         // Update vcpkg.json if it exists (optional)
-        self.update_vcpkg_version(&package_path, &bumped_version)?;
+        self.update_vcpkg_version(&ctx.transaction, &package_path, &bumped_version)?;
 
         Ok(())
     }
 
+    fn dependencies(
+        &mut self,
+        root: &Path,
+        pkg_config: &PackageConfig,
+    ) -> Result<Vec<String>, ResolveError> {
+        let vcpkg_path = root.join(&pkg_config.path).join("vcpkg.json");
+        if !vcpkg_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let vcpkg_json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&vcpkg_path)?).map_err(|e| {
+                ResolveError::ParseError {
+                    path: vcpkg_path.clone(),
+                    reason: e.to_string(),
+                }
+            })?;
+        // Dependencies are either port names or objects with a `name` field
+        Ok(vcpkg_json
+            .get("dependencies")
+            .and_then(|deps| deps.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|dep| dep.as_str().or_else(|| dep.get("name")?.as_str()))
+            .map(String::from)
+            .collect())
+    }
+
     fn sort_packages(
         &mut self,
         _root: &Path,
@@ -2,8 +2,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     changeset::Changeset,
-    config::{PackageConfig, ResolverConfig},
+    config::{PackageConfig, ResolverConfig, VersionSource},
     context::Context,
+    conventional,
     error::ResolveError,
     utils,
 };
@@ -60,6 +61,12 @@ pub trait Resolver {
         package: &ResolvedPackage,
         version: &semver::Version,
     ) -> Result<(), ResolveError>;
+    /// Names of the direct dependencies of a package
+    fn dependencies(
+        &mut self,
+        root: &Path,
+        pkg_config: &PackageConfig,
+    ) -> Result<Vec<String>, ResolveError>;
     /// Sort packages by their dependencies
     fn sort_packages(
         &mut self,
@@ -101,6 +108,17 @@ pub fn get_changeset_path() -> Result<PathBuf, ResolveError> {
 }
 
 pub fn get_changesets(ctx: &Context) -> Result<Vec<Changeset>, ResolveError> {
+    if ctx
+        .config
+        .as_ref()
+        .is_some_and(|c| c.version_source == VersionSource::Commits)
+    {
+        let repo = ctx.git_repo.as_ref().ok_or(ResolveError::GitError {
+            message: "The commits version source requires a git repository".to_string(),
+        })?;
+        return conventional::commit_changesets(ctx, repo);
+    }
+
     if let Some(changeset_root) = ctx.changeset_root.as_ref() {
         let mut changesets = Vec::new();
         utils::list_files(changeset_root, |p| p.extension() == Some("md".as_ref()))?
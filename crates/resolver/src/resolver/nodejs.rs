@@ -106,6 +106,7 @@ impl Resolver for NodejsResolver {
                     resolver: ResolverType::Nodejs,
                     version_mode: VersionMode::Semantic,
                     assets: vec![],
+                    contributors: None,
                 },
             )?;
             return Ok(vec![package]);
@@ -121,6 +122,7 @@ impl Resolver for NodejsResolver {
                 resolver: ResolverType::Nodejs,
                 version_mode: VersionMode::Semantic,
                 assets: vec![],
+                contributors: None,
             },
         ) {
             packages.push(root_package);
@@ -152,6 +154,7 @@ impl Resolver for NodejsResolver {
                             resolver: ResolverType::Nodejs,
                             version_mode: VersionMode::Semantic,
                             assets: vec![],
+                            contributors: None,
                         },
                     ) {
                         Ok(package) => packages.push(package),
@@ -175,7 +178,7 @@ impl Resolver for NodejsResolver {
     ) -> Result<(), ResolveError> {
         let bumped_version = version.to_string();
         let package_json_path = root.join(&package.path).join("package.json");
-        let package_json_str = std::fs::read_to_string(&package_json_path)?;
+        let package_json_str = ctx.transaction.read_to_string(&package_json_path)?;
 
         let mut package_json: serde_json::Value =
             serde_json::from_str(&package_json_str).map_err(|e| ResolveError::ParseError {
@@ -189,23 +192,56 @@ impl Resolver for NodejsResolver {
             *version = serde_json::Value::String(bumped_version.clone());
         }
 
+        // Snapshot releases pin internal dependencies to exact versions
+        if ctx.is_snapshot()
+            && let Some(obj) = package_json.as_object_mut()
+        {
+            for key in ["dependencies", "devDependencies", "peerDependencies"] {
+                let Some(deps) = obj.get_mut(key).and_then(|d| d.as_object_mut()) else {
+                    continue;
+                };
+                for (name, bumped_version) in ctx.version_bumps.borrow().iter() {
+                    if let Some(dep) = deps.get_mut(name)
+                        && dep.as_str().is_some_and(|r| !r.starts_with("workspace:"))
+                    {
+                        *dep = serde_json::Value::String(bumped_version.to_string());
+                    }
+                }
+            }
+        }
+
         let package_json_content =
             serde_json::to_string_pretty(&package_json).map_err(|e| ResolveError::ParseError {
                 path: package_json_path.clone(),
                 reason: e.to_string(),
             })?;
-        if !ctx.dry_run {
-            std::fs::write(package_json_path, package_json_content)?;
-        } else {
-            log::warn!(
-                "Skip bump for {} to version {} due to dry run",
-                package.name,
-                bumped_version
-            );
-        }
+        ctx.transaction
+            .write(package_json_path, package_json_content);
         Ok(())
     }
 
+    fn dependencies(
+        &mut self,
+        root: &Path,
+        pkg_config: &PackageConfig,
+    ) -> Result<Vec<String>, ResolveError> {
+        let package_json_path = root.join(&pkg_config.path).join("package.json");
+        let package_json: PackageJson =
+            serde_json::from_str(&std::fs::read_to_string(&package_json_path)?).map_err(|e| {
+                ResolveError::ParseError {
+                    path: package_json_path.clone(),
+                    reason: e.to_string(),
+                }
+            })?;
+        // Development dependencies are not part of the published package
+        Ok(package_json
+            .dependencies
+            .into_iter()
+            .chain(package_json.peer_dependencies)
+            .flat_map(|deps| deps.into_keys())
+            .collect())
+    }
+
     fn sort_packages(
         &mut self,
         root: &Path,
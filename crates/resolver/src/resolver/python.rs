@@ -10,6 +10,7 @@ use crate::{
     context,
     error::ResolveError,
     resolver::{ResolvedPackage, Resolver, ResolverType},
+    transaction::Transaction,
     utils,
 };
 
@@ -197,12 +198,13 @@ impl PythonResolver {
 
     fn update_pyproject_version(
         &self,
+        transaction: &Transaction,
         root: &Path,
         pkg_path: &Path,
         version: &str,
     ) -> Result<(), ResolveError> {
         let pyproject_path = root.join(pkg_path).join("pyproject.toml");
-        let pyproject_str = std::fs::read_to_string(&pyproject_path)?;
+        let pyproject_str = transaction.read_to_string(&pyproject_path)?;
 
         let mut doc = pyproject_str
             .parse::<toml_edit::DocumentMut>()
@@ -226,10 +228,10 @@ impl PythonResolver {
             poetry_table.insert("version", toml_edit::value(version));
         }
 
-        std::fs::write(&pyproject_path, doc.to_string())?;
+        transaction.write(&pyproject_path, doc.to_string());
 
         // 如果存在 Cargo.toml（maturin/PyO3 项目），也更新它
-        self.update_cargo_version(root, pkg_path, version)?;
+        self.update_cargo_version(transaction, root, pkg_path, version)?;
 
         Ok(())
     }
@@ -237,6 +239,7 @@ impl PythonResolver {
     /// 更新 Cargo.toml 中的版本号（用于 maturin/PyO3 项目）
     fn update_cargo_version(
         &self,
+        transaction: &Transaction,
         root: &Path,
         pkg_path: &Path,
         version: &str,
@@ -244,13 +247,13 @@ impl PythonResolver {
         let cargo_path = root.join(pkg_path).join("Cargo.toml");
 
         // 如果没有 Cargo.toml，不是错误，直接返回
-        if !cargo_path.exists() {
+        if !transaction.exists(&cargo_path) {
             return Ok(());
         }
 
         log::debug!("Found Cargo.toml, updating version for maturin/PyO3 project");
 
-        let cargo_str = std::fs::read_to_string(&cargo_path)?;
+        let cargo_str = transaction.read_to_string(&cargo_path)?;
         let mut doc =
             cargo_str
                 .parse::<toml_edit::DocumentMut>()
@@ -263,7 +266,7 @@ impl PythonResolver {
             && let Some(package_table) = package.as_table_mut()
         {
             package_table.insert("version", toml_edit::value(version));
-            std::fs::write(&cargo_path, doc.to_string())?;
+            transaction.write(&cargo_path, doc.to_string());
             log::info!("Updated version in Cargo.toml to {}", version);
         }
 
@@ -412,6 +415,7 @@ impl PythonResolver {
     /// 仅当 `__version__` 是硬编码的版本号字符串时才更新
     fn update_init_version(
         &self,
+        transaction: &Transaction,
         root: &Path,
         pkg_path: &Path,
         package_name: &str,
@@ -428,11 +432,11 @@ impl PythonResolver {
         ];
 
         for init_path in init_paths {
-            if !init_path.exists() {
+            if !transaction.exists(&init_path) {
                 continue;
             }
 
-            let content = std::fs::read_to_string(&init_path)?;
+            let content = transaction.read_to_string(&init_path)?;
             let mut new_content = String::new();
             let mut updated = false;
 
@@ -491,7 +495,7 @@ impl PythonResolver {
             }
 
             if updated {
-                std::fs::write(&init_path, new_content)?;
+                transaction.write(&init_path, new_content);
                 log::info!("Updated __version__ in {}", init_path.display());
                 return Ok(());
             } else {
@@ -583,6 +587,7 @@ impl Resolver for PythonResolver {
                     resolver: ResolverType::Python,
                     version_mode: VersionMode::Semantic,
                     assets: vec![],
+                    contributors: None,
                 },
             ) {
                 Ok(package) => packages.push(package),
@@ -606,6 +611,7 @@ impl Resolver for PythonResolver {
                                 resolver: ResolverType::Python,
                                 version_mode: VersionMode::Semantic,
                                 assets: vec![],
+                                contributors: None,
                             },
                         ) {
                             Ok(package) => packages.push(package),
@@ -630,26 +636,17 @@ impl Resolver for PythonResolver {
     ) -> Result<(), ResolveError> {
         let bumped_version = version.to_string();
 
-        if ctx.dry_run {
-            log::warn!(
-                "Skip bump for {} to version {} due to dry run",
-                package.name,
-                bumped_version
-            );
-            return Ok(());
-        }
-
         // 更新 pyproject.toml
         let pyproject_path = root.join(&package.path).join("pyproject.toml");
-        if pyproject_path.exists() {
-            self.update_pyproject_version(root, &package.path, &bumped_version)?;
+        if ctx.transaction.exists(&pyproject_path) {
+            self.update_pyproject_version(&ctx.transaction, root, &package.path, &bumped_version)?;
             log::info!("Updated pyproject.toml for {}", package.name);
         }
 
         // 更新 setup.cfg（如果存在）
         let setup_cfg_path = root.join(&package.path).join("setup.cfg");
-        if setup_cfg_path.exists() {
-            let content = std::fs::read_to_string(&setup_cfg_path)?;
+        if ctx.transaction.exists(&setup_cfg_path) {
+            let content = ctx.transaction.read_to_string(&setup_cfg_path)?;
             let new_content = content
                 .lines()
                 .map(|line| {
@@ -661,21 +658,33 @@ impl Resolver for PythonResolver {
                 })
                 .collect::<Vec<_>>()
                 .join("\n");
-            std::fs::write(&setup_cfg_path, new_content)?;
+            ctx.transaction.write(&setup_cfg_path, new_content);
             log::info!("Updated setup.cfg for {}", package.name);
         }
 
         // 尝试更新 __init__.py 中的 __version__
         let package_dir_name = package.name.replace('-', "_");
-        if let Err(e) =
-            self.update_init_version(root, &package.path, &package_dir_name, &bumped_version)
-        {
+        if let Err(e) = self.update_init_version(
+            &ctx.transaction,
+            root,
+            &package.path,
+            &package_dir_name,
+            &bumped_version,
+        ) {
             log::debug!("Could not update __init__.py: {}", e);
         }
 
         Ok(())
     }
 
+    fn dependencies(
+        &mut self,
+        root: &Path,
+        pkg_config: &PackageConfig,
+    ) -> Result<Vec<String>, ResolveError> {
+        self.parse_dependencies(root, &pkg_config.path)
+    }
+
     fn sort_packages(
         &mut self,
         root: &Path,
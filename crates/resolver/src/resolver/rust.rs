@@ -94,6 +94,7 @@ impl Resolver for RustResolver {
                     resolver: ResolverType::Rust,
                     version_mode: VersionMode::Semantic,
                     assets: vec![],
+                    contributors: None,
                 },
             )?;
             return Ok(vec![package]);
@@ -128,6 +129,7 @@ impl Resolver for RustResolver {
                         resolver: ResolverType::Rust,
                         version_mode: VersionMode::Semantic,
                         assets: vec![],
+                        contributors: None,
                     },
                 )
             })
@@ -145,7 +147,7 @@ impl Resolver for RustResolver {
     ) -> Result<(), ResolveError> {
         let bumped_version = version.to_string();
         let cargo_toml_path = root.join(&package.path).join("Cargo.toml");
-        let toml_str = std::fs::read_to_string(&cargo_toml_path)?;
+        let toml_str = ctx.transaction.read_to_string(&cargo_toml_path)?;
 
         let mut toml_doc =
             toml_str
@@ -173,23 +175,38 @@ impl Resolver for RustResolver {
             if let Some(dep) = deps_table.get_mut(name)
                 && dep["version"].is_str()
             {
-                dep["version"] = toml_edit::value(bumped_version.to_string());
+                // Snapshot releases pin internal dependencies to exact versions
+                let requirement = if ctx.is_snapshot() {
+                    format!("={bumped_version}")
+                } else {
+                    bumped_version.to_string()
+                };
+                dep["version"] = toml_edit::value(requirement);
             }
         }
 
         let toml_content = toml_doc.to_string();
-        if !ctx.dry_run {
-            std::fs::write(cargo_toml_path, toml_content)?;
-        } else {
-            log::warn!(
-                "Skip bump for {} to version {} due to dry run",
-                package.name,
-                bumped_version
-            );
-        }
+        ctx.transaction.write(cargo_toml_path, toml_content);
         Ok(())
     }
 
+    fn dependencies(
+        &mut self,
+        root: &Path,
+        pkg_config: &PackageConfig,
+    ) -> Result<Vec<String>, ResolveError> {
+        let toml_path = root.join(&pkg_config.path).join("Cargo.toml");
+        let cargo_toml: CargoToml = toml_edit::de::from_str(&std::fs::read_to_string(&toml_path)?)
+            .map_err(|e| ResolveError::ParseError {
+                path: toml_path.clone(),
+                reason: e.to_string(),
+            })?;
+        Ok(cargo_toml
+            .dependencies
+            .map(|deps| deps.into_keys().collect())
+            .unwrap_or_default())
+    }
+
     fn sort_packages(
         &mut self,
         root: &Path,
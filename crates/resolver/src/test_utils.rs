@@ -0,0 +1,71 @@
+//! Fixtures shared by the tests of the crate.
+
+use std::path::{Path, PathBuf};
+
+use git2::{Oid, Repository};
+
+use crate::config::Config;
+
+/// Config of Rust packages given as `(name, path)`, with the given tags.
+pub(crate) fn config(packages: &[(&str, &str)], tags: &[(&str, &str)]) -> Config {
+    let mut content = String::from(
+        "resolver = {}\n\n[branches]\nbase = \"main\"\nrelease = \"release\"\n\n[tags]\n",
+    );
+    for (tag, title) in tags {
+        content.push_str(&format!("{tag} = \"{title}\"\n"));
+    }
+    content.push_str("\n[packages]\n");
+    for (name, path) in packages {
+        content.push_str(&format!(
+            "{name} = {{ path = \"{path}\", resolver = \"rust\" }}\n"
+        ));
+    }
+    toml_edit::de::from_str(&content).unwrap()
+}
+
+/// Create an empty directory in the temp dir, named after the test.
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("semifold-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Create an empty repository in the temp dir, named after the test.
+pub(crate) fn temp_repo(name: &str) -> (PathBuf, Repository) {
+    let dir = temp_dir(name);
+    let repo = Repository::init(&dir).unwrap();
+    (dir, repo)
+}
+
+/// Commit changes of files, `None` contents remove the file.
+pub(crate) fn commit(repo: &Repository, files: &[(&str, Option<&str>)], message: &str) -> Oid {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for (path, content) in files {
+        match content {
+            Some(content) => {
+                std::fs::create_dir_all(workdir.join(path).parent().unwrap()).unwrap();
+                std::fs::write(workdir.join(path), content).unwrap();
+                index.add_path(Path::new(path)).unwrap();
+            }
+            None => {
+                std::fs::remove_file(workdir.join(path)).unwrap();
+                index.remove_path(Path::new(path)).unwrap();
+            }
+        }
+    }
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = git2::Signature::now("user", "user@example.com").unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    repo.commit(
+        Some("HEAD"),
+        &sig,
+        &sig,
+        message,
+        &tree,
+        parent.as_ref().into_iter().collect::<Vec<_>>().as_slice(),
+    )
+    .unwrap()
+}
@@ -0,0 +1,280 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+
+use crate::error::ResolveError;
+
+/// File writes staged in memory until they are committed together.
+///
+/// Reads through the transaction see the staged content, so later steps of a
+/// run build on the writes of earlier ones.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    /// Staged content of each file in write order, `None` for removed files.
+    staged: RefCell<IndexMap<PathBuf, Option<String>>>,
+}
+
+/// Original content of the files of a committed transaction.
+#[derive(Debug)]
+pub struct Committed {
+    originals: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{name}.semifold-tmp"))
+}
+
+/// Write a file through a temporary file renamed over it.
+fn replace_file(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let temp = temp_path(path);
+    std::fs::write(&temp, content)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+/// Read the content of a file before it is written, `None` if it is missing.
+fn read_original(path: &Path) -> Result<Option<Vec<u8>>, ResolveError> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Restore files to their original content, returning the first error.
+fn restore(originals: &[(PathBuf, Option<Vec<u8>>)]) -> Result<(), ResolveError> {
+    let mut result = Ok(());
+    for (path, original) in originals.iter().rev() {
+        let restored = match original {
+            Some(content) => replace_file(path, content),
+            None if path.exists() => std::fs::remove_file(path),
+            None => Ok(()),
+        };
+        if let Err(e) = restored {
+            log::error!("Failed to restore {}: {}", path.display(), e);
+            if result.is_ok() {
+                result = Err(e.into());
+            }
+        }
+    }
+    result
+}
+
+impl Transaction {
+    pub fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String, ResolveError> {
+        let path = path.as_ref();
+        match self.staged.borrow().get(path) {
+            Some(Some(content)) => Ok(content.clone()),
+            Some(None) => Err(ResolveError::FileOrDirNotFound {
+                path: path.to_path_buf(),
+            }),
+            None => Ok(std::fs::read_to_string(path)?),
+        }
+    }
+
+    pub fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        match self.staged.borrow().get(path) {
+            Some(staged) => staged.is_some(),
+            None => path.exists(),
+        }
+    }
+
+    pub fn write<P: AsRef<Path>, C: Into<String>>(&self, path: P, content: C) {
+        self.staged
+            .borrow_mut()
+            .insert(path.as_ref().to_path_buf(), Some(content.into()));
+    }
+
+    pub fn remove<P: AsRef<Path>>(&self, path: P) {
+        self.staged
+            .borrow_mut()
+            .insert(path.as_ref().to_path_buf(), None);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.staged.borrow().is_empty()
+    }
+
+    /// Run `f` against the staged writes and discard the writes it stages,
+    /// returning the paths it wrote or removed.
+    pub fn scratch<R>(&self, f: impl FnOnce() -> R) -> (R, Vec<PathBuf>) {
+        let saved = self.staged.borrow().clone();
+        let result = f();
+        let staged = std::mem::replace(&mut *self.staged.borrow_mut(), saved);
+        let saved = self.staged.borrow();
+        let paths = staged
+            .into_iter()
+            .filter(|(path, content)| saved.get(path) != Some(content))
+            .map(|(path, _)| path)
+            .collect();
+        (result, paths)
+    }
+
+    /// Discard the staged files, returning them in write order with `None`
+    /// for removed files.
+    pub fn take(&self) -> Vec<(PathBuf, Option<String>)> {
+        std::mem::take(&mut *self.staged.borrow_mut())
+            .into_iter()
+            .collect()
+    }
+
+    /// Apply the staged writes, leaving the files untouched on failure.
+    ///
+    /// Contents are first written to temporary files next to their targets,
+    /// which are then renamed over them, so a file is either fully written
+    /// or not at all. If a step fails, the files already replaced are
+    /// restored to their original content.
+    pub fn commit(&self) -> Result<Committed, ResolveError> {
+        let staged = std::mem::take(&mut *self.staged.borrow_mut());
+        let mut originals = Vec::with_capacity(staged.len());
+        for path in staged.keys() {
+            originals.push((path.clone(), read_original(path)?));
+        }
+
+        let mut temps = Vec::new();
+        let remove_temps = |temps: &[PathBuf]| {
+            for temp in temps {
+                let _ = std::fs::remove_file(temp);
+            }
+        };
+        for (path, content) in &staged {
+            let Some(content) = content else {
+                continue;
+            };
+            let temp = temp_path(path);
+            if let Err(e) = std::fs::write(&temp, content) {
+                remove_temps(&temps);
+                return Err(e.into());
+            }
+            temps.push(temp);
+        }
+
+        for (applied, (path, content)) in staged.iter().enumerate() {
+            let result = match content {
+                Some(_) => std::fs::rename(temp_path(path), path),
+                None if path.exists() => std::fs::remove_file(path),
+                None => Ok(()),
+            };
+            if let Err(e) = result {
+                remove_temps(&temps);
+                // The write error is returned, a failed restore is only logged
+                if let Err(restore_error) = restore(&originals[..applied]) {
+                    log::error!("Failed to restore written files: {restore_error}");
+                }
+                return Err(e.into());
+            }
+        }
+
+        Ok(Committed { originals })
+    }
+}
+
+impl Committed {
+    /// Record the current content of files which may be changed after the
+    /// commit, e.g. by commands run afterwards, so that they are restored by
+    /// a rollback too.
+    pub fn track<P: AsRef<Path>>(
+        &mut self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<(), ResolveError> {
+        for path in paths {
+            let path = path.as_ref();
+            if !self.originals.iter().any(|(p, _)| p == path) {
+                self.originals
+                    .push((path.to_path_buf(), read_original(path)?));
+            }
+        }
+        Ok(())
+    }
+
+    /// Restore the files of the transaction to their original content.
+    pub fn rollback(self) -> Result<(), ResolveError> {
+        restore(&self.originals)
+    }
+
+    /// Number of files changed by the transaction.
+    pub fn len(&self) -> usize {
+        self.originals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.originals.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn commits_and_rolls_back() {
+        let dir = temp_dir("transaction-commit");
+        let (kept, added, removed) = (dir.join("a"), dir.join("b"), dir.join("c"));
+        std::fs::write(&kept, "old").unwrap();
+        std::fs::write(&removed, "removed").unwrap();
+
+        let transaction = Transaction::default();
+        transaction.write(&kept, "new");
+        transaction.write(&added, "added");
+        transaction.remove(&removed);
+        assert_eq!(transaction.read_to_string(&kept).unwrap(), "new");
+        assert!(!transaction.exists(&removed));
+        assert_eq!(std::fs::read_to_string(&kept).unwrap(), "old");
+
+        let committed = transaction.commit().unwrap();
+        assert!(transaction.is_empty());
+        assert_eq!(std::fs::read_to_string(&kept).unwrap(), "new");
+        assert_eq!(std::fs::read_to_string(&added).unwrap(), "added");
+        assert!(!removed.exists());
+
+        committed.rollback().unwrap();
+        assert_eq!(std::fs::read_to_string(&kept).unwrap(), "old");
+        assert!(!added.exists());
+        assert_eq!(std::fs::read_to_string(&removed).unwrap(), "removed");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn leaves_files_untouched_on_failure() {
+        let dir = temp_dir("transaction-failure");
+        let kept = dir.join("a");
+        std::fs::write(&kept, "old").unwrap();
+
+        let transaction = Transaction::default();
+        transaction.write(&kept, "new");
+        transaction.write(dir.join("missing").join("b"), "content");
+        assert!(transaction.commit().is_err());
+
+        assert_eq!(std::fs::read_to_string(&kept).unwrap(), "old");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rolls_back_tracked_files() {
+        let dir = temp_dir("transaction-track");
+        let (written, lockfile, created) = (dir.join("a"), dir.join("a.lock"), dir.join("b.lock"));
+        std::fs::write(&lockfile, "old").unwrap();
+
+        let transaction = Transaction::default();
+        transaction.write(&written, "new");
+        let mut committed = transaction.commit().unwrap();
+        committed.track([&written, &lockfile, &created]).unwrap();
+        assert_eq!(committed.len(), 3);
+        std::fs::write(&lockfile, "new").unwrap();
+        std::fs::write(&created, "new").unwrap();
+
+        committed.rollback().unwrap();
+        assert!(!written.exists());
+        assert_eq!(std::fs::read_to_string(&lockfile).unwrap(), "old");
+        assert!(!created.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use git2::{DiffOptions, Oid, Repository};
 use semver::Version;
 
 use crate::{
@@ -48,7 +49,8 @@ pub fn list_files<F: Fn(&Path) -> bool>(
     Ok(files)
 }
 
-fn bump_prerelease(version: &mut Version, tag: &str) -> Result<(), ResolveError> {
+/// Bump the pre-release number of a version, or start a new pre-release with `tag`.
+pub fn bump_prerelease(version: &mut Version, tag: &str) -> Result<(), ResolveError> {
     if version.pre.is_empty() {
         version.pre = semver::Prerelease::new(&format!("{tag}.0"))?;
     } else {
@@ -75,32 +77,56 @@ fn bump_prerelease(version: &mut Version, tag: &str) -> Result<(), ResolveError>
     Ok(())
 }
 
+/// Get the level of the release a pre-release version leads to.
+///
+/// For example, `2.0.0-beta.3` leads to a major release, `1.5.0-rc.1` to a
+/// minor release and `1.5.1-rc.0` to a patch release. Stable versions are
+/// [`BumpLevel::Unchanged`].
+pub fn prerelease_level(version: &Version) -> BumpLevel {
+    if version.pre.is_empty() {
+        BumpLevel::Unchanged
+    } else if version.minor == 0 && version.patch == 0 {
+        BumpLevel::Major
+    } else if version.patch == 0 {
+        BumpLevel::Minor
+    } else {
+        BumpLevel::Patch
+    }
+}
+
 pub fn bump_version<'a>(
     version: &'a mut Version,
     level: BumpLevel,
     mode: &VersionMode,
 ) -> Result<&'a mut Version, ResolveError> {
     match mode {
+        // Explicit versions are already the target of a semantic release
+        VersionMode::Semantic if level == BumpLevel::Exact => {}
+        VersionMode::PreRelease { .. } if level == BumpLevel::Exact && !version.pre.is_empty() => {}
         VersionMode::Semantic => {
-            if version.pre.is_empty() {
-                match level {
-                    BumpLevel::Major => {
-                        version.major += 1;
-                        version.minor = 0;
-                        version.patch = 0;
-                    }
-                    BumpLevel::Minor => {
-                        version.minor += 1;
-                        version.patch = 0;
-                    }
-                    BumpLevel::Patch => {
-                        version.patch += 1;
-                    }
-                    BumpLevel::Unchanged => {}
-                }
-            } else {
-                // If the version is a pre-release, bumping semantic version resets pre-release
+            if !version.pre.is_empty() && level != BumpLevel::Unchanged {
+                // A pre-release already represents an upcoming release, which is
+                // only bumped further if the pending level is higher than its own
+                let prerelease_level = prerelease_level(version);
                 version.pre = semver::Prerelease::EMPTY;
+                if level <= prerelease_level {
+                    return Ok(version);
+                }
+            }
+            match level {
+                BumpLevel::Major => {
+                    version.major += 1;
+                    version.minor = 0;
+                    version.patch = 0;
+                }
+                BumpLevel::Minor => {
+                    version.minor += 1;
+                    version.patch = 0;
+                }
+                BumpLevel::Patch => {
+                    version.patch += 1;
+                }
+                BumpLevel::Exact | BumpLevel::Unchanged => {}
             }
         }
         VersionMode::PreRelease { tag } => {
@@ -116,7 +142,10 @@ pub fn bump_version<'a>(
     Ok(version)
 }
 
-pub fn get_bump_level(changesets: &[Changeset], package_name: &str) -> BumpLevel {
+pub fn get_bump_level<'a, I>(changesets: I, package_name: &str) -> BumpLevel
+where
+    I: IntoIterator<Item = &'a Changeset>,
+{
     let mut level = BumpLevel::Unchanged;
     for changeset in changesets {
         changeset.packages.iter().for_each(|package| {
@@ -128,6 +157,121 @@ pub fn get_bump_level(changesets: &[Changeset], package_name: &str) -> BumpLevel
     level
 }
 
+/// Get the explicit version of a package set by changesets, if any.
+///
+/// Different explicit versions for the same package are rejected.
+pub fn get_explicit_version<'a, I>(
+    changesets: I,
+    package_name: &str,
+) -> Result<Option<Version>, ResolveError>
+where
+    I: IntoIterator<Item = &'a Changeset>,
+{
+    let mut explicit: Option<(&Version, &Changeset)> = None;
+    for changeset in changesets {
+        for package in &changeset.packages {
+            let (true, Some(version)) = (package.name == package_name, &package.version) else {
+                continue;
+            };
+            match explicit {
+                Some((other, other_changeset)) if other != version => {
+                    return Err(ResolveError::InvalidChangeset {
+                        path: changeset.path.clone().unwrap_or_default(),
+                        reason: format!(
+                            "Conflicting explicit versions for {package_name}: {other} in {} and {version} in {}",
+                            other_changeset.name, changeset.name
+                        ),
+                    });
+                }
+                _ => explicit = Some((version, changeset)),
+            }
+        }
+    }
+    Ok(explicit.map(|(version, _)| version.clone()))
+}
+
+/// Prefix of the release tags of a package, which are named
+/// `<package>-v<version>`.
+pub fn release_tag_prefix(package_name: &str) -> String {
+    format!("{package_name}-v")
+}
+
+/// Name of the release tag of a package version.
+pub fn release_tag_name(package_name: &str, version: &str) -> String {
+    format!("{}{version}", release_tag_prefix(package_name))
+}
+
+/// Find the most recent release tag reachable from `HEAD`.
+pub fn find_last_release_tag<'a>(
+    repo: &Repository,
+    package_names: impl IntoIterator<Item = &'a str>,
+) -> Option<Oid> {
+    let head = repo.head().ok()?.peel_to_commit().ok()?.id();
+    let prefixes = package_names
+        .into_iter()
+        .map(release_tag_prefix)
+        .collect::<Vec<_>>();
+
+    let tag_names = repo.tag_names(None).ok()?;
+    tag_names
+        .iter()
+        .flatten()
+        .filter(|tag| {
+            prefixes.iter().any(|prefix| {
+                tag.strip_prefix(prefix.as_str())
+                    .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+            })
+        })
+        .filter_map(|tag| {
+            repo.revparse_single(&format!("refs/tags/{tag}"))
+                .and_then(|o| o.peel_to_commit())
+                .ok()
+        })
+        .filter(|commit| {
+            commit.id() == head || repo.graph_descendant_of(head, commit.id()).unwrap_or(false)
+        })
+        .max_by_key(|commit| commit.time().seconds())
+        .map(|commit| commit.id())
+}
+
+/// Commits touching `path` reachable from `to` but not from `from`, oldest
+/// first. Merge commits are skipped.
+pub fn commits_touching(
+    repo: &Repository,
+    from: Option<Oid>,
+    to: Oid,
+    path: &Path,
+) -> Result<Vec<Oid>, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(to)?;
+    if let Some(from) = from {
+        revwalk.hide(from)?;
+    }
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.pathspec(path);
+        let diff =
+            repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut diff_opts))?;
+        if diff.deltas().len() > 0 {
+            commits.push(commit.id());
+        }
+    }
+    Ok(commits)
+}
+
 pub fn run_command(command: &CommandConfig, cwd: &Path) -> Result<(), ResolveError> {
     let mut cmd = std::process::Command::new(&command.command);
     if let Some(args) = &command.args {
@@ -148,3 +292,94 @@ pub fn run_command(command: &CommandConfig, cwd: &Path) -> Result<(), ResolveErr
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bump(version: &str, level: BumpLevel, mode: &VersionMode) -> String {
+        let mut version = Version::parse(version).unwrap();
+        bump_version(&mut version, level, mode).unwrap();
+        version.to_string()
+    }
+
+    fn semantic(version: &str, level: BumpLevel) -> String {
+        bump(version, level, &VersionMode::Semantic)
+    }
+
+    #[test]
+    fn test_prerelease_level() {
+        let level = |v: &str| prerelease_level(&Version::parse(v).unwrap());
+        assert_eq!(level("1.2.3"), BumpLevel::Unchanged);
+        assert_eq!(level("2.0.0-beta.3"), BumpLevel::Major);
+        assert_eq!(level("0.0.0-snapshot"), BumpLevel::Major);
+        assert_eq!(level("1.5.0-rc.1"), BumpLevel::Minor);
+        assert_eq!(level("0.5.0-alpha"), BumpLevel::Minor);
+        assert_eq!(level("1.5.1-rc.0"), BumpLevel::Patch);
+    }
+
+    #[test]
+    fn test_semantic_bump_stable() {
+        assert_eq!(semantic("1.2.3", BumpLevel::Major), "2.0.0");
+        assert_eq!(semantic("1.2.3", BumpLevel::Minor), "1.3.0");
+        assert_eq!(semantic("1.2.3", BumpLevel::Patch), "1.2.4");
+        assert_eq!(semantic("1.2.3", BumpLevel::Unchanged), "1.2.3");
+        assert_eq!(semantic("1.2.3", BumpLevel::Exact), "1.2.3");
+    }
+
+    #[test]
+    fn test_semantic_bump_major_prerelease() {
+        assert_eq!(semantic("2.0.0-beta.3", BumpLevel::Major), "2.0.0");
+        assert_eq!(semantic("2.0.0-beta.3", BumpLevel::Minor), "2.0.0");
+        assert_eq!(semantic("2.0.0-beta.3", BumpLevel::Patch), "2.0.0");
+        assert_eq!(
+            semantic("2.0.0-beta.3", BumpLevel::Unchanged),
+            "2.0.0-beta.3"
+        );
+    }
+
+    #[test]
+    fn test_semantic_bump_minor_prerelease() {
+        assert_eq!(semantic("1.5.0-rc.1", BumpLevel::Major), "2.0.0");
+        assert_eq!(semantic("1.5.0-rc.1", BumpLevel::Minor), "1.5.0");
+        assert_eq!(semantic("1.5.0-rc.1", BumpLevel::Patch), "1.5.0");
+        assert_eq!(semantic("1.5.0-rc.1", BumpLevel::Unchanged), "1.5.0-rc.1");
+    }
+
+    #[test]
+    fn test_semantic_bump_patch_prerelease() {
+        assert_eq!(semantic("1.5.1-rc.0", BumpLevel::Major), "2.0.0");
+        assert_eq!(semantic("1.5.1-rc.0", BumpLevel::Minor), "1.6.0");
+        assert_eq!(semantic("1.5.1-rc.0", BumpLevel::Patch), "1.5.1");
+        assert_eq!(semantic("1.5.1-rc.0", BumpLevel::Unchanged), "1.5.1-rc.0");
+    }
+
+    #[test]
+    fn test_semantic_bump_exact() {
+        assert_eq!(semantic("2.0.0", BumpLevel::Exact), "2.0.0");
+        assert_eq!(semantic("2.0.0-rc.0", BumpLevel::Exact), "2.0.0-rc.0");
+    }
+
+    #[test]
+    fn test_prerelease_bump() {
+        let beta = VersionMode::PreRelease {
+            tag: "beta".to_string(),
+        };
+        assert_eq!(bump("1.0.0", BumpLevel::Patch, &beta), "1.0.0-beta.0");
+        assert_eq!(
+            bump("1.0.0-beta.0", BumpLevel::Minor, &beta),
+            "1.0.0-beta.1"
+        );
+        assert_eq!(bump("1.0.0-beta", BumpLevel::Patch, &beta), "1.0.0-beta.1");
+        assert_eq!(
+            bump("1.0.0-alpha.2", BumpLevel::Major, &beta),
+            "1.0.0-beta.0"
+        );
+        assert_eq!(bump("2.0.0", BumpLevel::Exact, &beta), "2.0.0-beta.0");
+        assert_eq!(bump("2.0.0-rc.1", BumpLevel::Exact, &beta), "2.0.0-rc.1");
+
+        let mut version = Version::parse("1.0.0").unwrap();
+        let empty = VersionMode::PreRelease { tag: String::new() };
+        assert!(bump_version(&mut version, BumpLevel::Patch, &empty).is_err());
+    }
+}
@@ -1,9 +1,13 @@
-use clap::{Parser, Subcommand, builder::styling};
+use clap::{Parser, Subcommand, ValueEnum, builder::styling};
 use rust_i18n::t;
 
+pub mod changelog;
+pub mod changeset;
+pub mod check;
 pub mod ci;
 pub mod commit;
 pub mod init;
+pub mod pre;
 pub mod publish;
 pub mod status;
 pub mod version;
@@ -22,6 +26,29 @@ pub(crate) enum Commands {
     CI(ci::CI),
     #[command(about = t!("cli.commands.status"))]
     Status(status::Status),
+    #[command(about = t!("cli.commands.check"))]
+    Check(check::Check),
+    #[command(about = t!("cli.commands.changeset"))]
+    Changeset(changeset::ChangesetCommand),
+    #[command(about = t!("cli.commands.pre"))]
+    Pre(pre::Pre),
+    #[command(about = t!("cli.commands.changelog"))]
+    Changelog(changelog::ChangelogCommand),
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    /// Human-readable output
+    #[default]
+    Text,
+    /// JSON output following the schema of `semifold_resolver::plan`
+    Json,
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        self == OutputFormat::Json
+    }
 }
 
 fn get_styles() -> clap::builder::Styles {
@@ -43,4 +70,7 @@ pub(crate) struct Cli {
 
     #[arg(global = true, long, help = t!("cli.flags.debug"))]
     pub debug: bool,
+
+    #[arg(global = true, long, value_enum, default_value_t = OutputFormat::Text, help = t!("cli.flags.format"))]
+    pub format: OutputFormat,
 }
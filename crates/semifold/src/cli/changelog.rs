@@ -0,0 +1,267 @@
+use clap::{Args, Parser, Subcommand};
+use colored::Colorize;
+use rust_i18n::t;
+use semifold_changelog::{
+    backfill::backfill_changelog,
+    build_commit_index, create_pr_resolver, generate_changelog,
+    parser::{parse_changelog, read_changelog},
+    types::Release,
+};
+use semifold_resolver::{
+    context::Context,
+    plan::{create_plan, pending_changesets},
+    resolver,
+};
+
+use crate::cli::OutputFormat;
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum ChangelogCommands {
+    #[command(about = t!("cli.changelog.commands.show"))]
+    Show(Show),
+    #[command(about = t!("cli.changelog.commands.preview"))]
+    Preview(Preview),
+    #[command(about = t!("cli.changelog.commands.backfill"))]
+    Backfill(Backfill),
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct ChangelogCommand {
+    #[command(subcommand)]
+    pub command: ChangelogCommands,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct Show {
+    #[arg(help = t!("cli.changelog.flags.package"))]
+    pub package: String,
+    #[arg(value_name = "VERSION|RANGE", help = t!("cli.changelog.flags.version"))]
+    pub version: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct Preview {
+    #[arg(help = t!("cli.changelog.flags.preview_package"))]
+    pub package: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct Backfill {
+    #[arg(help = t!("cli.changelog.flags.package"))]
+    pub package: String,
+    #[arg(long, default_value_t = false, help = t!("cli.changelog.flags.conventional"))]
+    pub conventional: bool,
+    #[arg(long, default_value_t = false, help = t!("cli.changelog.flags.force"))]
+    pub force: bool,
+}
+
+/// Select releases by an exact version, a `FROM..TO` range or a semver
+/// requirement, the latest release when nothing is given.
+fn select_releases(releases: Vec<Release>, query: Option<&str>) -> anyhow::Result<Vec<Release>> {
+    let Some(query) = query.map(str::trim) else {
+        return Ok(releases.into_iter().take(1).collect());
+    };
+    let parse = |version: &str| semver::Version::parse(version.trim().trim_start_matches('v'));
+    let invalid = || anyhow::anyhow!(t!("cli.changelog.invalid_query", query = query));
+
+    let matches: Box<dyn Fn(&semver::Version) -> bool> =
+        if let Some((from, to)) = query.split_once("..") {
+            // Releases after `FROM` up to and including `TO`, like git ranges
+            let from = (!from.is_empty())
+                .then(|| parse(from))
+                .transpose()
+                .map_err(|_| invalid())?;
+            let to = (!to.is_empty())
+                .then(|| parse(to))
+                .transpose()
+                .map_err(|_| invalid())?;
+            Box::new(move |v| {
+                from.as_ref().is_none_or(|from| v > from) && to.as_ref().is_none_or(|to| v <= to)
+            })
+        } else if let Ok(version) = parse(query) {
+            Box::new(move |v| v == &version)
+        } else {
+            let req = semver::VersionReq::parse(query).map_err(|_| invalid())?;
+            Box::new(move |v| req.matches(v))
+        };
+
+    Ok(releases
+        .into_iter()
+        .filter(|release| parse(&release.version).is_ok_and(|v| matches(&v)))
+        .collect())
+}
+
+fn print_releases(releases: &[Release], format: OutputFormat) -> anyhow::Result<()> {
+    if format.is_json() {
+        println!("{}", serde_json::to_string_pretty(releases)?);
+    } else {
+        let bodies = releases.iter().map(|r| r.body.as_str()).collect::<Vec<_>>();
+        println!("{}", bodies.join("\n\n"));
+    }
+    Ok(())
+}
+
+fn show(opts: &Show, format: OutputFormat, ctx: &Context) -> anyhow::Result<()> {
+    let root = ctx.repo_root.clone().unwrap_or(std::env::current_dir()?);
+    let Some(package_config) = ctx.get_package_config(&opts.package) else {
+        return Err(anyhow::anyhow!(t!(
+            "cli.changelog.unknown_package",
+            package = &opts.package
+        )));
+    };
+
+    let changelog_path = root.join(&package_config.path).join("CHANGELOG.md");
+    if !changelog_path.exists() {
+        return Err(anyhow::anyhow!(t!(
+            "cli.changelog.not_found",
+            package = &opts.package
+        )));
+    }
+    let changelog = read_changelog(&changelog_path)?;
+    let releases = select_releases(changelog.releases, opts.version.as_deref())?;
+    if releases.is_empty() {
+        return Err(anyhow::anyhow!(t!(
+            "cli.changelog.no_releases",
+            package = &opts.package
+        )));
+    }
+
+    print_releases(&releases, format)
+}
+
+async fn preview(opts: &Preview, format: OutputFormat, ctx: &Context) -> anyhow::Result<()> {
+    let Some(repo) = ctx.git_repo.as_ref() else {
+        return Err(anyhow::anyhow!(t!("cli.version.no_git_repo")));
+    };
+    if let Some(package) = &opts.package
+        && !ctx.has_package(package)
+    {
+        return Err(anyhow::anyhow!(t!(
+            "cli.changelog.unknown_package",
+            package = package
+        )));
+    }
+
+    let changesets = resolver::get_changesets(ctx)?;
+    let pending = pending_changesets(ctx, &changesets);
+    let commit_index = build_commit_index(ctx, repo, &pending)?;
+    let mut pr_resolver = create_pr_resolver(ctx);
+    if let Some(pr_resolver) = pr_resolver.as_mut() {
+        pr_resolver.prefetch(commit_index.commits()).await?;
+    }
+
+    let plan = create_plan(ctx, &changesets)?;
+    // Dependents list the versions their dependencies are released with
+    ctx.version_bumps.borrow_mut().extend(
+        plan.releases
+            .iter()
+            .map(|r| (r.name.clone(), r.next_version.clone())),
+    );
+    let mut previews = Vec::new();
+    for release in &plan.releases {
+        if opts.package.as_ref().is_some_and(|p| p != &release.name) {
+            continue;
+        }
+        let changelog = generate_changelog(
+            ctx,
+            &commit_index,
+            pr_resolver.as_mut(),
+            &pending,
+            &release.name,
+            &release.next_version.to_string(),
+        )
+        .await?;
+        previews.push((release.name.clone(), changelog));
+    }
+
+    if format.is_json() {
+        let json = previews
+            .iter()
+            .map(|(package, changelog)| {
+                let release = parse_changelog(&format!("# Changelog\n\n{changelog}"))
+                    .map_err(|reason| anyhow::anyhow!(reason))?
+                    .releases
+                    .into_iter()
+                    .next();
+                Ok(serde_json::json!({ "package": package, "release": release }))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
+    if previews.is_empty() {
+        println!("{}", t!("cli.changelog.no_pending"));
+        return Ok(());
+    }
+    let previews = previews
+        .iter()
+        .map(|(package, changelog)| format!("{}\n\n{}", package.cyan().bold(), changelog))
+        .collect::<Vec<_>>();
+    println!("{}", previews.join("\n\n"));
+
+    Ok(())
+}
+
+async fn backfill(opts: &Backfill, ctx: &Context) -> anyhow::Result<()> {
+    let root = ctx.repo_root.clone().unwrap_or(std::env::current_dir()?);
+    let Some(repo) = ctx.git_repo.as_ref() else {
+        return Err(anyhow::anyhow!(t!("cli.version.no_git_repo")));
+    };
+    let Some(package_config) = ctx.get_package_config(&opts.package) else {
+        return Err(anyhow::anyhow!(t!(
+            "cli.changelog.unknown_package",
+            package = &opts.package
+        )));
+    };
+
+    let changelog_path = root.join(&package_config.path).join("CHANGELOG.md");
+    if changelog_path.exists() && !opts.force {
+        return Err(anyhow::anyhow!(t!(
+            "cli.changelog.already_exists",
+            path = changelog_path.display()
+        )));
+    }
+
+    let (changelog, releases) =
+        backfill_changelog(ctx, repo, &opts.package, opts.conventional).await?;
+    if releases == 0 {
+        return Err(anyhow::anyhow!(t!(
+            "cli.changelog.no_tags",
+            package = &opts.package
+        )));
+    }
+
+    if ctx.dry_run {
+        log::warn!("{}", t!("cli.changelog.skip_backfill_dry_run"));
+        print!("{changelog}");
+        return Ok(());
+    }
+    std::fs::write(&changelog_path, changelog)?;
+    log::info!(
+        "{}",
+        t!(
+            "cli.changelog.backfilled",
+            count = releases,
+            path = changelog_path.display().to_string().cyan()
+        )
+    );
+
+    Ok(())
+}
+
+pub(crate) async fn run(
+    opts: &ChangelogCommand,
+    ctx: &Context,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    if !ctx.is_initialized() {
+        return Err(anyhow::anyhow!(t!("cli.not_initialized")));
+    };
+
+    match &opts.command {
+        ChangelogCommands::Show(show_opts) => show(show_opts, format, ctx),
+        ChangelogCommands::Preview(preview_opts) => preview(preview_opts, format, ctx).await,
+        ChangelogCommands::Backfill(backfill_opts) => backfill(backfill_opts, ctx).await,
+    }
+}
@@ -0,0 +1,308 @@
+use std::path::{Component, Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+use colored::Colorize;
+use inquire::Confirm;
+use rust_i18n::t;
+use semifold_resolver::{
+    changeset::{BumpLevel, ChangePackage, Changeset},
+    context::Context,
+    resolver,
+};
+
+use crate::cli::OutputFormat;
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum ChangesetCommands {
+    #[command(about = t!("cli.changeset.commands.list"), visible_alias = "ls")]
+    List,
+    #[command(about = t!("cli.changeset.commands.show"))]
+    Show(Target),
+    #[command(about = t!("cli.changeset.commands.edit"))]
+    Edit(Target),
+    #[command(about = t!("cli.changeset.commands.remove"), visible_alias = "rm")]
+    Remove(Remove),
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct ChangesetCommand {
+    #[command(subcommand)]
+    pub command: ChangesetCommands,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct Target {
+    #[arg(help = t!("cli.changeset.flags.name"))]
+    pub name: String,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct Remove {
+    #[arg(help = t!("cli.changeset.flags.name"))]
+    pub name: String,
+    #[arg(short, long, default_value_t = false, help = t!("cli.changeset.flags.yes"))]
+    pub yes: bool,
+}
+
+fn format_level(package: &ChangePackage) -> colored::ColoredString {
+    match package.level {
+        BumpLevel::Exact => package.mark().magenta(),
+        BumpLevel::Major => package.mark().red(),
+        BumpLevel::Minor => package.mark().yellow(),
+        BumpLevel::Patch => package.mark().cyan(),
+        BumpLevel::Unchanged => package.mark().dimmed(),
+    }
+}
+
+/// Whether a changeset name is a plain file name, which cannot point outside
+/// of the changeset root.
+fn is_valid_name(name: &str) -> bool {
+    !name.contains(['/', '\\'])
+        && matches!(
+            Path::new(name).components().collect::<Vec<_>>().as_slice(),
+            [Component::Normal(_)]
+        )
+}
+
+fn find_changeset(ctx: &Context, name: &str) -> anyhow::Result<PathBuf> {
+    if !is_valid_name(name) {
+        return Err(anyhow::anyhow!(t!(
+            "cli.changeset.invalid_name",
+            name = name
+        )));
+    }
+    let changeset_root = ctx.changeset_root.as_ref().unwrap();
+    let path = changeset_root.join(format!("{name}.md"));
+    if !path.is_file() {
+        return Err(anyhow::anyhow!(t!("cli.changeset.not_found", name = name)));
+    }
+    Ok(path)
+}
+
+fn to_json(changeset: &Changeset) -> serde_json::Value {
+    serde_json::json!({
+        "name": changeset.name,
+        "packages": changeset
+            .packages
+            .iter()
+            .map(|p| serde_json::json!({
+                "name": p.name,
+                "level": p.level,
+                "tag": p.tag,
+                "version": p.version,
+            }))
+            .collect::<Vec<_>>(),
+        "issues": changeset.issues,
+        "breaking": changeset.breaking,
+        "authors": changeset.authors,
+        "pr": changeset.pr,
+        "summary": changeset.summary,
+    })
+}
+
+fn list(format: OutputFormat, ctx: &Context) -> anyhow::Result<()> {
+    let mut changesets = resolver::get_changesets(ctx)?;
+    changesets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if format.is_json() {
+        let json = changesets.iter().map(to_json).collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
+    if changesets.is_empty() {
+        println!("{}", t!("cli.changeset.empty"));
+        return Ok(());
+    }
+
+    let name_width = changesets.iter().map(|c| c.name.len()).max().unwrap_or(0) + 1;
+    for changeset in &changesets {
+        let packages = changeset
+            .packages
+            .iter()
+            .map(|p| format!("{}:{}", p.name, format_level(p)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut tags = changeset
+            .packages
+            .iter()
+            .filter_map(|p| p.tag.as_deref())
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        let summary = changeset.summary.lines().next().unwrap_or_default();
+        println!(
+            "{:name_width$} {} [{}] {}",
+            changeset.name.cyan().bold(),
+            packages,
+            tags.join(", ").magenta(),
+            summary
+        );
+    }
+
+    Ok(())
+}
+
+fn show(opts: &Target, ctx: &Context) -> anyhow::Result<()> {
+    let path = find_changeset(ctx, &opts.name)?;
+    let changeset = Changeset::parse_file(&path)?;
+    for package in &changeset.packages {
+        if !ctx.has_package(&package.name) {
+            log::warn!(
+                "{}",
+                t!(
+                    "cli.check.unknown_package",
+                    changeset = changeset.name.cyan(),
+                    package = package.name.yellow()
+                )
+            );
+        }
+    }
+
+    println!("{}\n", changeset.name.cyan().bold());
+    for package in &changeset.packages {
+        if let Some(tag) = &package.tag {
+            println!(
+                "{} {} ({})",
+                package.name.bold(),
+                format_level(package),
+                tag.magenta()
+            );
+        } else {
+            println!("{} {}", package.name.bold(), format_level(package));
+        }
+    }
+    if !changeset.issues.is_empty() {
+        let issues = changeset
+            .issues
+            .iter()
+            .map(|issue| format!("#{issue}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("\n{} {}", t!("cli.changeset.issues").bold(), issues);
+    }
+    if let Some(pr) = changeset.pr {
+        println!("{} #{}", t!("cli.changeset.pr").bold(), pr);
+    }
+    if !changeset.authors.is_empty() {
+        let authors = changeset
+            .authors
+            .iter()
+            .map(|author| format!("@{author}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("{} {}", t!("cli.changeset.authors").bold(), authors);
+    }
+    if let Some(breaking) = &changeset.breaking {
+        println!("{} {}", t!("cli.changeset.breaking").red().bold(), breaking);
+    }
+    println!("\n{}", changeset.summary);
+
+    Ok(())
+}
+
+fn edit(opts: &Target, ctx: &Context) -> anyhow::Result<()> {
+    let path = find_changeset(ctx, &opts.name)?;
+    let original = std::fs::read_to_string(&path)?;
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+
+    loop {
+        let mut parts = editor.split_whitespace();
+        let program = parts.next().unwrap_or(&editor);
+        let status = std::process::Command::new(program)
+            .args(parts)
+            .arg(&path)
+            .status()?;
+        if !status.success() {
+            std::fs::write(&path, &original)?;
+            return Err(anyhow::anyhow!(t!(
+                "cli.changeset.editor_failed",
+                editor = &editor
+            )));
+        }
+
+        match Changeset::from_file(ctx, &path) {
+            Ok(_) => {
+                log::info!("{}", t!("cli.changeset.edited", name = opts.name.cyan()));
+                return Ok(());
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                if !Confirm::new(&t!("cli.changeset.query_reedit"))
+                    .with_default(true)
+                    .prompt()?
+                {
+                    std::fs::write(&path, &original)?;
+                    return Err(anyhow::anyhow!(t!(
+                        "cli.changeset.edit_reverted",
+                        name = &opts.name
+                    )));
+                }
+            }
+        }
+    }
+}
+
+fn remove(opts: &Remove, ctx: &Context) -> anyhow::Result<()> {
+    let path = find_changeset(ctx, &opts.name)?;
+
+    if !opts.yes
+        && !Confirm::new(&t!("cli.changeset.query_remove", name = opts.name.as_str()))
+            .with_default(false)
+            .prompt()?
+    {
+        return Ok(());
+    }
+
+    std::fs::remove_file(&path)?;
+    log::info!("{}", t!("cli.changeset.removed", name = opts.name.cyan()));
+
+    Ok(())
+}
+
+pub(crate) fn run(
+    opts: &ChangesetCommand,
+    ctx: &Context,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    if !ctx.is_initialized() {
+        return Err(anyhow::anyhow!(t!("cli.not_initialized")));
+    };
+
+    match &opts.command {
+        ChangesetCommands::List => list(format, ctx),
+        ChangesetCommands::Show(show_opts) => show(show_opts, ctx),
+        ChangesetCommands::Edit(edit_opts) => edit(edit_opts, ctx),
+        ChangesetCommands::Remove(remove_opts) => remove(remove_opts, ctx),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_names_outside_changeset_root() {
+        assert!(is_valid_name("add-feature"));
+        assert!(is_valid_name("fix..typo"));
+        for name in [
+            "",
+            ".",
+            "..",
+            "../../foo",
+            "nested/foo",
+            "..\\foo",
+            "/etc/passwd",
+        ] {
+            assert!(!is_valid_name(name), "{name} should be rejected");
+        }
+    }
+}
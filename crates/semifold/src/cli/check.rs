@@ -0,0 +1,128 @@
+use std::collections::BTreeSet;
+
+use clap::Parser;
+use colored::Colorize;
+use rust_i18n::t;
+use semifold_changelog::template::Templates;
+use semifold_resolver::{
+    changeset::{self, Changeset},
+    context::Context,
+    utils,
+};
+
+#[derive(Parser, Debug)]
+pub(crate) struct Check;
+
+/// Validate changesets against the config and collect every problem found.
+fn check_changesets(ctx: &Context, problems: &mut Vec<String>) -> anyhow::Result<usize> {
+    let config = ctx.config.as_ref().unwrap();
+    let changeset_root = ctx.changeset_root.as_ref().unwrap();
+
+    let paths = utils::list_files(changeset_root, |p| p.extension() == Some("md".as_ref()))?;
+    for path in &paths {
+        let changeset = match Changeset::parse_file(path) {
+            Ok(changeset) => changeset,
+            Err(e) => {
+                problems.push(e.to_string());
+                continue;
+            }
+        };
+        log::debug!("Checking changeset at: {}", path.display());
+
+        for package in &changeset.packages {
+            if !ctx.has_package(&package.name) {
+                problems.push(
+                    t!(
+                        "cli.check.unknown_package",
+                        changeset = changeset.name.cyan(),
+                        package = package.name.yellow()
+                    )
+                    .to_string(),
+                );
+            }
+            if let Some(tag) = &package.tag
+                && !config.tags.contains_key(tag)
+            {
+                problems.push(
+                    t!(
+                        "cli.check.unknown_tag",
+                        changeset = changeset.name.cyan(),
+                        tag = tag.yellow()
+                    )
+                    .to_string(),
+                );
+            }
+        }
+    }
+
+    Ok(paths.len())
+}
+
+/// Validate that every package resolves and has a resolver config.
+fn check_packages(ctx: &Context, problems: &mut Vec<String>) -> anyhow::Result<()> {
+    let root = ctx.repo_root.clone().unwrap_or(std::env::current_dir()?);
+
+    let mut missing_resolvers = BTreeSet::new();
+    for (package_name, package_config) in ctx.get_packages() {
+        if changeset::METADATA_KEYS.contains(&package_name.as_str()) {
+            problems
+                .push(t!("cli.check.reserved_package", package = package_name.cyan()).to_string());
+        }
+        let mut resolver = ctx.create_resolver(package_config.resolver);
+        if let Err(e) = resolver.resolve(&root, package_config) {
+            problems.push(
+                t!(
+                    "cli.check.unresolved_package",
+                    package = package_name.cyan(),
+                    reason = e.to_string()
+                )
+                .to_string(),
+            );
+        }
+        if ctx.get_resolver_config(package_config.resolver).is_none() {
+            missing_resolvers.insert(package_config.resolver);
+        }
+    }
+
+    for resolver in missing_resolvers {
+        problems.push(
+            t!(
+                "cli.check.missing_resolver_config",
+                resolver = resolver.to_string().cyan()
+            )
+            .to_string(),
+        );
+    }
+
+    Ok(())
+}
+
+pub(crate) fn run(_check: &Check, ctx: &Context) -> anyhow::Result<()> {
+    if !ctx.is_initialized() {
+        return Err(anyhow::anyhow!(t!("cli.not_initialized")));
+    };
+
+    let mut problems = Vec::new();
+    let count = check_changesets(ctx, &mut problems)?;
+    check_packages(ctx, &mut problems)?;
+    if let Err(e) = Templates::new(&ctx.config.as_ref().unwrap().changelog) {
+        problems.push(e.to_string());
+    }
+
+    if problems.is_empty() {
+        log::info!(
+            "{}",
+            t!("cli.check.passed", count = count.to_string().bold())
+        );
+        return Ok(());
+    }
+
+    for problem in &problems {
+        log::error!("{}", problem);
+    }
+
+    Err(anyhow::anyhow!(t!(
+        "cli.check.failed",
+        count = problems.len()
+    )))
+}
@@ -6,7 +6,11 @@ use git2::{Cred, IndexAddOption, PushOptions, RemoteCallbacks, Repository};
 use octocrab::{Octocrab, params};
 use rust_i18n::t;
 
-use semifold_resolver::{context::Context, resolver};
+use semifold_resolver::{
+    context::Context,
+    plan::{create_plan, pending_changesets},
+    resolver,
+};
 
 use crate::cli::{publish, version};
 
@@ -78,12 +82,14 @@ pub(crate) async fn run(_ci: &CI, ctx: &Context) -> anyhow::Result<()> {
     }
 
     let changesets = resolver::get_changesets(ctx)?;
-    if changesets.is_empty() {
+    if pending_changesets(ctx, &changesets).is_empty() {
         log::info!("No changesets found, will publish the current version.");
-        return publish::publish(ctx, true).await;
+        let report = publish::publish(ctx, true, None).await?;
+        return publish::ensure_published(&report);
     }
 
-    let changelogs_map = version::version(ctx, &changesets).await?;
+    let plan = create_plan(ctx, &changesets)?;
+    let plan = version::version(ctx, &changesets, &plan).await?;
 
     let head = repo.head()?;
     let commit = head.peel_to_commit()?;
@@ -129,9 +135,9 @@ pub(crate) async fn run(_ci: &CI, ctx: &Context) -> anyhow::Result<()> {
     let pr_title = "chore(release): bump versions";
     let pr_body = format!(
         "# Releases\n\n{}",
-        changelogs_map
+        plan.releases
             .into_iter()
-            .map(|(name, changelog)| { format!("## {name}\n\n{changelog}") })
+            .filter_map(|r| Some(format!("## {}\n\n{}", r.name, r.changelog?)))
             .collect::<Vec<_>>()
             .join("\n\n")
     );
@@ -7,6 +7,8 @@ use inquire::{Autocomplete, Confirm, MultiSelect, Text, autocompletion::Replacem
 use rust_i18n::t;
 use semifold_resolver::{changeset, context::Context};
 
+use crate::cli::status;
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub(crate) enum Level {
     Patch,
@@ -42,6 +44,82 @@ pub(crate) struct Commit {
     pub level: Option<Level>,
     #[arg(short, long, help = t!("cli.commit.flags.summary"))]
     pub summary: Option<String>,
+    #[arg(
+        short,
+        long = "package",
+        value_name = "PACKAGE[:LEVEL[:TAG]]",
+        help = t!("cli.commit.flags.package")
+    )]
+    pub packages: Vec<String>,
+    #[arg(short, long, help = t!("cli.commit.flags.tag"))]
+    pub tag: Option<String>,
+    #[arg(short, long, default_value_t = false, help = t!("cli.commit.flags.yes"))]
+    pub yes: bool,
+    #[arg(long, value_name = "NUMBER", help = t!("cli.commit.flags.pr"))]
+    pub pr: Option<u64>,
+    #[arg(long = "author", value_name = "LOGIN", help = t!("cli.commit.flags.author"))]
+    pub authors: Vec<String>,
+    #[arg(long, default_value_t = false, help = t!("cli.commit.flags.stamp"))]
+    pub stamp: bool,
+}
+
+/// Package selected from the command line, level and tag may be left to defaults.
+#[derive(Debug)]
+struct PackageSpec {
+    name: String,
+    level: Option<changeset::BumpLevel>,
+    version: Option<semver::Version>,
+    tag: Option<String>,
+}
+
+fn parse_level(level: &str, package: &str) -> anyhow::Result<PackageSpec> {
+    let invalid_level = || {
+        anyhow::anyhow!(t!(
+            "cli.commit.invalid_level",
+            level = level,
+            package = package
+        ))
+    };
+    let (level, version) = if let Some(version) = level.strip_prefix('=') {
+        let version = semver::Version::parse(version).map_err(|_| invalid_level())?;
+        (changeset::BumpLevel::Exact, Some(version))
+    } else if level.eq_ignore_ascii_case("none") {
+        (changeset::BumpLevel::Unchanged, None)
+    } else {
+        let level = Level::from_str(level, true).map_err(|_| invalid_level())?;
+        (level.to_bump_level(), None)
+    };
+    Ok(PackageSpec {
+        name: package.to_string(),
+        level: Some(level),
+        version,
+        tag: None,
+    })
+}
+
+fn parse_package_spec(spec: &str) -> anyhow::Result<PackageSpec> {
+    let mut parts = spec.splitn(3, ':');
+    let name = parts.next().unwrap_or_default().trim().to_string();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!(t!(
+            "cli.commit.invalid_package_spec",
+            spec = spec
+        )));
+    }
+    let mut spec = match parts.next().filter(|l| !l.is_empty()) {
+        Some(level) => parse_level(level, &name)?,
+        None => PackageSpec {
+            name,
+            level: None,
+            version: None,
+            tag: None,
+        },
+    };
+    spec.tag = parts
+        .next()
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string());
+    Ok(spec)
 }
 
 #[derive(Clone)]
@@ -108,6 +186,35 @@ pub(crate) fn run(commit: &Commit, ctx: &Context) -> anyhow::Result<()> {
         return Err(anyhow::anyhow!(t!("cli.not_initialized")));
     };
 
+    let mut specs = commit
+        .packages
+        .iter()
+        .map(|spec| parse_package_spec(spec))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    for (idx, spec) in specs.iter().enumerate() {
+        if !config.packages.contains_key(&spec.name) {
+            return Err(anyhow::anyhow!(t!(
+                "cli.commit.unknown_package",
+                package = &spec.name
+            )));
+        }
+        if specs[..idx].iter().any(|s| s.name == spec.name) {
+            return Err(anyhow::anyhow!(t!(
+                "cli.commit.duplicate_package",
+                package = &spec.name
+            )));
+        }
+    }
+    for tag in specs
+        .iter()
+        .filter_map(|s| s.tag.as_ref())
+        .chain(commit.tag.as_ref())
+    {
+        if !config.tags.contains_key(tag) {
+            return Err(anyhow::anyhow!(t!("cli.commit.unknown_tag", tag = tag)));
+        }
+    }
+
     let name = if let Some(name) = &commit.name {
         let sanitized_name = sanitize_filename(name);
         if sanitized_name.is_empty() {
@@ -117,6 +224,8 @@ pub(crate) fn run(commit: &Commit, ctx: &Context) -> anyhow::Result<()> {
             return Err(anyhow::anyhow!(t!("cli.commit.commit_exists", name = name)));
         }
         sanitized_name
+    } else if commit.yes {
+        return Err(anyhow::anyhow!(t!("cli.commit.missing_name")));
     } else {
         loop {
             let name = Text::new(&t!("cli.commit.query_name"))
@@ -132,26 +241,70 @@ pub(crate) fn run(commit: &Commit, ctx: &Context) -> anyhow::Result<()> {
 
     log::debug!("Change name: {name}");
 
-    let mut packages = loop {
-        let packages = MultiSelect::new(
-            &t!("cli.commit.query_packages"),
-            config.packages.keys().cloned().collect::<Vec<_>>(),
-        )
-        .prompt()?;
-        if packages.is_empty() {
-            log::warn!("{}", t!("cli.commit.warn_no_packages"));
-            continue;
+    if specs.is_empty() {
+        if commit.yes {
+            return Err(anyhow::anyhow!(t!("cli.commit.missing_packages")));
         }
-        break packages;
-    };
+        let packages = loop {
+            let packages = MultiSelect::new(
+                &t!("cli.commit.query_packages"),
+                config.packages.keys().cloned().collect::<Vec<_>>(),
+            )
+            .prompt()?;
+            if packages.is_empty() {
+                log::warn!("{}", t!("cli.commit.warn_no_packages"));
+                continue;
+            }
+            break packages;
+        };
+        specs = packages
+            .into_iter()
+            .map(|name| PackageSpec {
+                name,
+                level: None,
+                version: None,
+                tag: None,
+            })
+            .collect();
+    }
 
-    let tag = Text::new(&t!("cli.commit.query_tags"))
-        .with_autocomplete(TagAutocomplete {
-            tags: config.tags.keys().cloned().collect::<Vec<_>>(),
-        })
-        .prompt()?;
+    for spec in specs.iter_mut().filter(|s| s.level.is_none()) {
+        if let Some(level) = &commit.level {
+            spec.level = Some(level.to_bump_level());
+        } else if commit.yes {
+            return Err(anyhow::anyhow!(t!(
+                "cli.commit.missing_level",
+                package = &spec.name
+            )));
+        }
+    }
+
+    let tag = if commit.tag.is_some() || commit.yes || specs.iter().all(|s| s.tag.is_some()) {
+        commit.tag.clone()
+    } else {
+        let tag = Text::new(&t!("cli.commit.query_tags"))
+            .with_autocomplete(TagAutocomplete {
+                tags: config.tags.keys().cloned().collect::<Vec<_>>(),
+            })
+            .prompt()?;
+        Some(tag.trim().to_string()).filter(|t| !t.is_empty())
+    };
 
     let mut changeset = changeset::Changeset::new(name.clone(), changeset_root);
+    for spec in specs.iter().filter(|s| s.level.is_some()) {
+        let package_tag = spec.tag.clone().or(tag.clone());
+        if let Some(version) = &spec.version {
+            changeset.add_package_version(spec.name.clone(), version.clone(), package_tag);
+        } else {
+            changeset.add_package(spec.name.clone(), spec.level.unwrap(), package_tag);
+        }
+    }
+
+    let mut packages = specs
+        .iter()
+        .filter(|s| s.level.is_none())
+        .map(|s| s.name.clone())
+        .collect::<Vec<_>>();
     let level_variants = Level::value_variants().iter().rev();
     for variant in level_variants {
         if packages.is_empty() {
@@ -184,11 +337,17 @@ pub(crate) fn run(commit: &Commit, ctx: &Context) -> anyhow::Result<()> {
             &[]
         })
         .prompt()?;
-        changeset.add_packages(
-            &selected_packages,
-            variant.to_bump_level(),
-            Some(tag.clone()),
-        );
+        for package in &selected_packages {
+            let package_tag = specs
+                .iter()
+                .find(|s| &s.name == package)
+                .and_then(|s| s.tag.clone());
+            changeset.add_package(
+                package.clone(),
+                variant.to_bump_level(),
+                package_tag.or(tag.clone()),
+            );
+        }
         packages.retain(|p| !selected_packages.contains(p));
     }
 
@@ -201,7 +360,12 @@ pub(crate) fn run(commit: &Commit, ctx: &Context) -> anyhow::Result<()> {
     }
 
     let summary = if let Some(summary) = &commit.summary {
+        if summary.trim().is_empty() {
+            return Err(anyhow::anyhow!(t!("cli.commit.empty_summary")));
+        }
         summary.clone()
+    } else if commit.yes {
+        return Err(anyhow::anyhow!(t!("cli.commit.missing_summary")));
     } else {
         loop {
             let summary = inquire::prompt_text(&t!("cli.commit.query_summary"))?;
@@ -214,7 +378,58 @@ pub(crate) fn run(commit: &Commit, ctx: &Context) -> anyhow::Result<()> {
     };
     changeset.summary(summary);
 
-    changeset.commit()?;
+    changeset.pr = commit.pr;
+    changeset.authors = commit
+        .authors
+        .iter()
+        .map(|author| author.trim_start_matches('@').to_string())
+        .collect();
+    if commit.stamp {
+        match status::read_github_event() {
+            Ok(event) => event.pull_request.stamp(&mut changeset),
+            Err(e) => log::warn!("{}", t!("cli.commit.stamp_failed", reason = e.to_string())),
+        }
+    }
+
+    changeset.commit_to(changeset_root)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_package_specs() {
+        let spec = parse_package_spec("core").unwrap();
+        assert_eq!(spec.name, "core");
+        assert!(spec.level.is_none() && spec.version.is_none() && spec.tag.is_none());
+
+        let spec = parse_package_spec("core:Minor:feat").unwrap();
+        assert_eq!(spec.level, Some(changeset::BumpLevel::Minor));
+        assert_eq!(spec.tag.as_deref(), Some("feat"));
+
+        let spec = parse_package_spec("core::fix").unwrap();
+        assert!(spec.level.is_none());
+        assert_eq!(spec.tag.as_deref(), Some("fix"));
+
+        let spec = parse_package_spec("core:none").unwrap();
+        assert_eq!(spec.level, Some(changeset::BumpLevel::Unchanged));
+
+        let spec = parse_package_spec("core:=2.0.0-rc.1").unwrap();
+        assert_eq!(spec.level, Some(changeset::BumpLevel::Exact));
+        assert_eq!(
+            spec.version,
+            Some(semver::Version::parse("2.0.0-rc.1").unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_package_specs() {
+        assert!(parse_package_spec("").is_err());
+        assert!(parse_package_spec(":patch").is_err());
+        assert!(parse_package_spec("core:huge").is_err());
+        assert!(parse_package_spec("core:=2.0").is_err());
+    }
+}
@@ -1,12 +1,13 @@
 use std::{collections::BTreeMap, path::PathBuf};
 
 use clap::{Args, ValueEnum};
+use indexmap::IndexMap;
 use inquire::{Confirm, MultiSelect, Select, Text};
 use rust_i18n::t;
 use semifold_resolver::{
     config::{
         self, BranchesConfig, CommandConfig, PackageConfig, PreCheckConfig, ResolverConfig,
-        VersionMode,
+        TagConfig, VersionMode,
     },
     context,
     error::ResolveError,
@@ -67,7 +68,11 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
     const AVAILABLE_TARGETS: [&str; 2] = [".changes", ".changesets"];
 
     let mut target_dir = std::env::current_dir()?;
-    if ctx.repo_root.is_some() && ctx.repo_root.as_ref().unwrap() != &target_dir {
+    if ctx
+        .repo_root
+        .as_ref()
+        .is_some_and(|repo_root| repo_root != &target_dir)
+    {
         log::warn!("{}", t!("cli.init.not_repo_root"));
         if !Confirm::new(&t!("cli.init.continue"))
             .with_default(false)
@@ -127,6 +132,7 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
                         stderr: config::StdioType::Inherit,
                         dry_run: Some(true),
                     }],
+                    dist_tag_args: None,
                 },
             ),
             ResolverType::Nodejs => (
@@ -147,7 +153,8 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
                         stderr: config::StdioType::Inherit,
                         dry_run: None,
                     }],
-                    post_version: vec![]
+                    post_version: vec![],
+                    dist_tag_args: Some(vec!["--tag".to_string(), "{tag}".to_string()]),
                 },
             ),
             ResolverType::Python => (
@@ -163,7 +170,8 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
                     },
                     prepublish: vec![],
                     publish: vec![],
-                    post_version: vec![]
+                    post_version: vec![],
+                    dist_tag_args: None,
                 },
             ),
         }
@@ -183,6 +191,7 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
                         resolver: resolver::ResolverType::Rust,
                         version_mode: VersionMode::Semantic,
                         assets: vec![],
+                        contributors: None,
                     });
                 });
                 Ok::<_, ResolveError>(acc)
@@ -196,6 +205,7 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
                         resolver: resolver::ResolverType::Nodejs,
                         version_mode: VersionMode::Semantic,
                         assets: vec![],
+                        contributors: None,
                     });
                 });
                 Ok::<_, ResolveError>(acc)
@@ -209,6 +219,7 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
                         resolver: resolver::ResolverType::Python,
                         version_mode: VersionMode::Semantic,
                         assets: vec![],
+                        contributors: None,
                     });
                 });
                 Ok::<_, ResolveError>(acc)
@@ -221,15 +232,18 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
         .with_default(true)
         .prompt()?
     {
-        BTreeMap::from_iter([
-            ("chore".to_string(), "Chores".to_string()),
-            ("feat".to_string(), "New Features".to_string()),
-            ("fix".to_string(), "Bug Fixes".to_string()),
-            ("perf".to_string(), "Performance Improvements".to_string()),
-            ("refactor".to_string(), "Refactors".to_string()),
-        ])
+        IndexMap::from_iter(
+            [
+                ("feat", "New Features"),
+                ("fix", "Bug Fixes"),
+                ("perf", "Performance Improvements"),
+                ("refactor", "Refactors"),
+                ("chore", "Chores"),
+            ]
+            .map(|(tag, title)| (tag.to_string(), TagConfig::Title(title.to_string()))),
+        )
     } else {
-        BTreeMap::default()
+        IndexMap::default()
     };
 
     let base_branch = if let Some(base_branch) = &init.base_branch {
@@ -256,6 +270,9 @@ pub(crate) fn run(init: &Init, ctx: &context::Context) -> anyhow::Result<()> {
         tags,
         packages,
         resolver: resolvers_config,
+        snapshot: Default::default(),
+        changelog: Default::default(),
+        version_source: Default::default(),
     };
 
     let write_ci = Confirm::new(&t!("cli.init.write_ci"))
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use clap::{Args, Parser, Subcommand};
+use colored::Colorize;
+use rust_i18n::t;
+use semifold_resolver::{
+    context::Context,
+    pre::{PreMode, PreState},
+};
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum PreCommands {
+    #[command(about = t!("cli.pre.commands.enter"))]
+    Enter(Enter),
+    #[command(about = t!("cli.pre.commands.exit"))]
+    Exit,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct Pre {
+    #[command(subcommand)]
+    pub command: PreCommands,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct Enter {
+    #[arg(help = t!("cli.pre.flags.tag"))]
+    pub tag: String,
+}
+
+fn enter(opts: &Enter, ctx: &Context) -> anyhow::Result<()> {
+    let changeset_root = ctx.changeset_root.as_ref().unwrap();
+
+    if opts.tag.is_empty() || semver::Prerelease::new(&opts.tag).is_err() {
+        return Err(anyhow::anyhow!(t!("cli.pre.invalid_tag", tag = &opts.tag)));
+    }
+
+    let pre_state = match &ctx.pre_state {
+        Some(pre_state) if pre_state.is_pre() => {
+            return Err(anyhow::anyhow!(t!(
+                "cli.pre.already_entered",
+                tag = &pre_state.tag
+            )));
+        }
+        // Re-entering before the stable release keeps the consumed changesets
+        Some(pre_state) => PreState {
+            mode: PreMode::Pre,
+            tag: opts.tag.clone(),
+            ..pre_state.clone()
+        },
+        None => {
+            let root = ctx.repo_root.clone().unwrap_or(std::env::current_dir()?);
+            let initial_versions = ctx.get_packages().into_iter().try_fold(
+                BTreeMap::new(),
+                |mut acc, (package_name, package_config)| {
+                    let mut resolver = ctx.create_resolver(package_config.resolver);
+                    let resolved_package = resolver.resolve(&root, package_config)?;
+                    acc.insert(package_name.clone(), resolved_package.version);
+                    Ok::<_, anyhow::Error>(acc)
+                },
+            )?;
+            PreState::new(opts.tag.clone(), initial_versions)
+        }
+    };
+
+    if ctx.dry_run {
+        log::warn!("{}", t!("cli.pre.skip_dry_run"));
+    } else {
+        pre_state.save(changeset_root)?;
+    }
+    log::info!("{}", t!("cli.pre.entered", tag = opts.tag.cyan()));
+
+    Ok(())
+}
+
+fn exit(ctx: &Context) -> anyhow::Result<()> {
+    let changeset_root = ctx.changeset_root.as_ref().unwrap();
+
+    let Some(pre_state) = ctx.pre_state.as_ref().filter(|s| s.is_pre()) else {
+        return Err(anyhow::anyhow!(t!("cli.pre.not_entered")));
+    };
+
+    let pre_state = PreState {
+        mode: PreMode::Exit,
+        ..pre_state.clone()
+    };
+    if ctx.dry_run {
+        log::warn!("{}", t!("cli.pre.skip_dry_run"));
+    } else {
+        pre_state.save(changeset_root)?;
+    }
+    log::info!("{}", t!("cli.pre.exited", tag = pre_state.tag.cyan()));
+
+    Ok(())
+}
+
+pub(crate) fn run(opts: &Pre, ctx: &Context) -> anyhow::Result<()> {
+    if !ctx.is_initialized() {
+        return Err(anyhow::anyhow!(t!("cli.not_initialized")));
+    };
+
+    match &opts.command {
+        PreCommands::Enter(enter_opts) => enter(enter_opts, ctx),
+        PreCommands::Exit => exit(ctx),
+    }
+}
@@ -13,15 +13,48 @@ use semifold_changelog::read_latest_changelog;
 use semifold_resolver::{
     config::{PackageConfig, ResolverConfig},
     context::Context,
-    resolver::ResolvedPackage,
+    plan::{PublishReport, PublishedPackage, SKIP_ALREADY_PUBLISHED, SKIP_PRIVATE},
+    resolver::{ResolvedPackage, ResolverType},
+    utils::release_tag_name,
 };
 
+use crate::cli::OutputFormat;
+
 #[derive(Debug, Parser)]
 pub(crate) struct Publish {
     #[clap(short = 'r', long, default_value_t = true, help = t!("cli.publish.flags.github_release"))]
     github_release: bool,
     #[clap(short = 'd', long, default_value_t = false, help = t!("cli.publish.flags.allow_dirty"))]
     allow_dirty: bool,
+    #[clap(short = 't', long, help = t!("cli.publish.flags.tag"))]
+    tag: Option<String>,
+}
+
+/// Append dist-tag arguments of the resolver to its publish commands.
+pub(crate) fn with_dist_tag(
+    resolver_config: &ResolverConfig,
+    resolver: &ResolverType,
+    tag: &str,
+) -> anyhow::Result<ResolverConfig> {
+    let Some(dist_tag_args) = &resolver_config.dist_tag_args else {
+        return Err(anyhow::anyhow!(t!(
+            "cli.publish.no_dist_tag_args",
+            resolver = resolver.to_string()
+        )));
+    };
+    let dist_tag_args = dist_tag_args
+        .iter()
+        .map(|arg| arg.replace("{tag}", tag))
+        .collect::<Vec<_>>();
+
+    let mut resolver_config = resolver_config.clone();
+    for command in &mut resolver_config.publish {
+        command
+            .args
+            .get_or_insert_with(Vec::new)
+            .extend(dist_tag_args.iter().cloned());
+    }
+    Ok(resolver_config)
 }
 
 pub(crate) async fn create_github_release(
@@ -44,9 +77,9 @@ pub(crate) async fn create_github_release(
     }
 
     let changelog = read_latest_changelog(&changelog_path).await?;
-    let tag_name = format!("{}-{}", package_name, changelog.version);
-    let release_title = format!("{} {}", package_name, changelog.version);
     let version = semver::Version::parse(&changelog.version[1..])?;
+    let tag_name = release_tag_name(package_name, &version.to_string());
+    let release_title = format!("{} {}", package_name, changelog.version);
 
     log::debug!("Tag name: {}", &tag_name);
     log::debug!("Changelog for {}:\n\n{}", &package_name, &changelog.body);
@@ -103,15 +136,21 @@ pub(crate) async fn pre_check(
     Ok(resp.status() == StatusCode::OK)
 }
 
-pub(crate) async fn publish(ctx: &Context, github_release: bool) -> anyhow::Result<()> {
+pub(crate) async fn publish(
+    ctx: &Context,
+    github_release: bool,
+    tag: Option<&str>,
+) -> anyhow::Result<PublishReport> {
     let config = ctx.config.as_ref().unwrap();
+    let mut report = PublishReport::new(ctx.dry_run);
 
     log::debug!(
         "Packages to publish: {:?}",
         &config.packages.keys().collect::<Vec<_>>()
     );
 
-    let should_create_github_release = ctx.is_ci() && github_release;
+    // Releases with a dist-tag are test builds, which never go to GitHub releases
+    let should_create_github_release = ctx.is_ci() && github_release && tag.is_none();
 
     let octocrab = if let Ok(token) = std::env::var("GITHUB_TOKEN") {
         octocrab::Octocrab::builder()
@@ -137,11 +176,20 @@ pub(crate) async fn publish(ctx: &Context, github_release: bool) -> anyhow::Resu
                 "Config for resolver {} not found",
                 &package.resolver
             ))?;
+        let resolver_config = &match tag {
+            Some(tag) => with_dist_tag(resolver_config, &package.resolver, tag)?,
+            None => resolver_config.clone(),
+        };
         log::debug!("Resolver config: {:?}", &resolver_config);
 
         let mut resolver = ctx.create_resolver(package.resolver);
         let resolved_package = resolver.resolve(&root, package)?;
         log::debug!("Resolved package: {}", &resolved_package.name);
+        let outcome = |reason: Option<String>| PublishedPackage {
+            name: package_name.clone(),
+            version: resolved_package.version.clone(),
+            reason,
+        };
 
         if pre_check(resolver_config, &resolved_package).await? {
             log::warn!(
@@ -152,11 +200,19 @@ pub(crate) async fn publish(ctx: &Context, github_release: bool) -> anyhow::Resu
                     version = format!("v{}", resolved_package.version).green()
                 )
             );
+            report
+                .skipped
+                .push(outcome(Some(SKIP_ALREADY_PUBLISHED.to_string())));
             continue;
         }
 
         if !resolved_package.private {
-            resolver.publish(&resolved_package, resolver_config, ctx.dry_run)?;
+            if let Err(e) = resolver.publish(&resolved_package, resolver_config, ctx.dry_run) {
+                // Stop at the first failure, dependents of the package can't be published
+                report.failed.push(outcome(Some(e.to_string())));
+                break;
+            }
+            report.published.push(outcome(None));
         } else {
             log::warn!(
                 "{}",
@@ -166,6 +222,7 @@ pub(crate) async fn publish(ctx: &Context, github_release: bool) -> anyhow::Resu
                     version = format!("v{}", resolved_package.version).green()
                 )
             );
+            report.skipped.push(outcome(Some(SKIP_PRIVATE.to_string())));
         }
 
         if should_create_github_release {
@@ -223,10 +280,22 @@ pub(crate) async fn publish(ctx: &Context, github_release: bool) -> anyhow::Resu
         }
     }
 
-    Ok(())
+    Ok(report)
 }
 
-pub(crate) async fn run(opts: &Publish, ctx: &Context) -> anyhow::Result<()> {
+/// Fail if a package of the report failed to publish.
+pub(crate) fn ensure_published(report: &PublishReport) -> anyhow::Result<()> {
+    match report.failed.first() {
+        Some(failed) => Err(anyhow::anyhow!(t!(
+            "cli.publish.failed",
+            package = &failed.name,
+            reason = failed.reason.as_deref().unwrap_or_default()
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub(crate) async fn run(opts: &Publish, ctx: &Context, format: OutputFormat) -> anyhow::Result<()> {
     if !ctx.is_initialized() {
         return Err(anyhow::anyhow!(t!("cli.not_initialized")));
     };
@@ -235,7 +304,10 @@ pub(crate) async fn run(opts: &Publish, ctx: &Context) -> anyhow::Result<()> {
         return Err(anyhow::anyhow!(t!("cli.dirty_repo")));
     }
 
-    publish(ctx, opts.github_release).await?;
+    let report = publish(ctx, opts.github_release, opts.tag.as_deref()).await?;
+    if format.is_json() {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
 
-    Ok(())
+    ensure_published(&report)
 }
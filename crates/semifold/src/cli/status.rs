@@ -1,4 +1,4 @@
-use std::{collections::HashMap, env};
+use std::env;
 
 use anyhow::Context as _;
 use clap::Parser;
@@ -6,10 +6,15 @@ use colored::Colorize;
 use octocrab::Octocrab;
 use rust_i18n::t;
 use semifold_resolver::{
-    changeset::BumpLevel, config::VersionMode, context::Context, resolver, utils,
+    changeset::Changeset,
+    context::Context,
+    plan::{ReleasePlan, create_plan},
+    resolver,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::cli::{OutputFormat, version};
+
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct RepoOwner {
     pub login: String,
@@ -32,6 +37,21 @@ pub(crate) struct PullRequest {
     pub number: u64,
     pub head: Branch,
     pub base: Branch,
+    #[serde(default)]
+    pub user: Option<RepoOwner>,
+}
+
+impl PullRequest {
+    /// Record the number and author of the pull request into a changeset,
+    /// keeping metadata which is already set.
+    pub(crate) fn stamp(&self, changeset: &mut Changeset) {
+        changeset.pr = changeset.pr.or(Some(self.number));
+        if changeset.authors.is_empty()
+            && let Some(user) = &self.user
+        {
+            changeset.authors.push(user.login.clone());
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -44,106 +64,214 @@ pub(crate) struct GitHubEvent {
 pub(crate) struct Status {
     #[arg(short, long, default_value_t = true, help = t!("cli.status.flags.comment"))]
     pub comment: bool,
+    #[arg(long, help = t!("cli.status.flags.since"))]
+    pub since: Option<String>,
+    #[arg(long, default_value_t = false, requires = "since", help = t!("cli.status.flags.strict"))]
+    pub strict: bool,
+    #[arg(long, default_value_t = false, help = t!("cli.status.flags.stamp"))]
+    pub stamp: bool,
 }
 
-pub(crate) async fn run(status: &Status, ctx: &Context) -> anyhow::Result<()> {
-    if !ctx.is_initialized() {
-        return Err(anyhow::anyhow!(t!("cli.not_initialized")));
+/// Read the pull request event of the current GitHub Actions run.
+pub(crate) fn read_github_event() -> anyhow::Result<GitHubEvent> {
+    let path = env::var("GITHUB_EVENT_PATH").context("no GITHUB_EVENT_PATH")?;
+    let event_data = std::fs::read_to_string(&path)?;
+
+    log::debug!("GITHUB_EVENT_PATH: {}", &path);
+    log::debug!("GITHUB_EVENT_PATH data: {}", &event_data);
+
+    Ok(serde_json::from_str(&event_data)?)
+}
+
+/// Record the pull request number and author into changesets added by it.
+fn stamp_changesets(
+    ctx: &Context,
+    changesets: &[Changeset],
+    pull_request: &PullRequest,
+    since: &str,
+) -> anyhow::Result<()> {
+    let changed_files = match ctx.get_changed_files(since) {
+        Ok(changed_files) => changed_files,
+        Err(e) => {
+            log::warn!("{}", t!("cli.status.stamp_failed", reason = e.to_string()));
+            return Ok(());
+        }
     };
 
-    let is_ci = ctx.is_ci();
-    log::debug!("GitHub CI environment: {}", is_ci);
+    let repo_root = ctx.repo_root.clone().unwrap_or(std::env::current_dir()?);
+    for changeset in changesets {
+        let Some(rel_path) = changeset
+            .path
+            .as_ref()
+            .and_then(|path| path.strip_prefix(&repo_root).ok())
+        else {
+            continue;
+        };
+        if changeset.pr.is_some() || !changed_files.iter().any(|f| f == rel_path) {
+            continue;
+        }
 
-    let root = ctx.repo_root.clone().unwrap_or(std::env::current_dir()?);
-    let config = ctx.config.as_ref().unwrap();
+        let mut changeset = changeset.clone();
+        pull_request.stamp(&mut changeset);
+        if ctx.dry_run {
+            log::info!(
+                "{}",
+                t!(
+                    "cli.status.skip_stamp_dry_run",
+                    name = changeset.name.cyan()
+                )
+            );
+            continue;
+        }
+        changeset.commit()?;
+        log::info!(
+            "{}",
+            t!(
+                "cli.status.stamped",
+                name = changeset.name.cyan(),
+                pr = pull_request.number
+            )
+        );
+    }
 
-    let changesets = resolver::get_changesets(ctx)?;
+    Ok(())
+}
+
+fn ensure_changesets(status: &Status, missing_packages: &[String]) -> anyhow::Result<()> {
+    if status.strict && !missing_packages.is_empty() {
+        return Err(anyhow::anyhow!(t!(
+            "cli.status.missing_changesets_error",
+            count = missing_packages.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Describe the dependencies a package is released for.
+fn bumped_by_message(dependencies: &[String]) -> String {
+    t!(
+        "cli.status.bumped_by",
+        dependencies = dependencies.join(", ")
+    )
+    .to_string()
+}
+
+fn print_plan(ctx: &Context, plan: &ReleasePlan) {
+    let config = ctx.config.as_ref().unwrap();
     let name_width = config.packages.keys().map(|s| s.len()).max().unwrap_or(0) + 1;
 
     println!(
         "{}\n",
         t!(
             "cli.status.changesets",
-            count = changesets.len().to_string().bold()
+            count = plan.changesets.len().to_string().bold()
         )
     );
 
-    let mut bump_map = HashMap::new();
-    let mut warnings = vec![];
-    for (package_name, package_config) in &config.packages {
-        let level = utils::get_bump_level(&changesets, package_name);
-        if matches!(level, BumpLevel::Unchanged) {
-            continue;
-        }
-
-        let mut resolver = ctx.create_resolver(package_config.resolver);
-        let resolved_package = resolver.resolve(&root, package_config)?;
-        let mut bumped_version = resolved_package.version.clone();
-        utils::bump_version(&mut bumped_version, level, &package_config.version_mode)?;
-
-        if matches!(package_config.version_mode, VersionMode::Semantic)
-            && !resolved_package.version.pre.is_empty()
-            && level != BumpLevel::Unchanged
-            && level != BumpLevel::Patch
-        {
-            log::debug!(
-                "Adding pre-release warning for package: {}",
-                package_name.as_str()
-            );
-            warnings.push(t!(
-                "cli.status.pre_release_warning",
-                package = package_name.as_str().cyan()
-            ));
-        }
-
-        bump_map.insert(
-            package_name,
-            (
-                level,
-                resolved_package.version.clone(),
-                bumped_version.clone(),
-            ),
-        );
+    if let Some(pre_state) = &ctx.pre_state {
+        let message = if pre_state.is_pre() {
+            t!("cli.status.pre_mode", tag = pre_state.tag.as_str().cyan())
+        } else {
+            t!(
+                "cli.status.pre_mode_exit",
+                tag = pre_state.tag.as_str().cyan()
+            )
+        };
+        println!("{}\n", message.yellow());
     }
 
-    if bump_map.is_empty() {
+    if plan.releases.is_empty() {
         println!("{}", t!("cli.status.no_packages"));
     } else {
         println!("{}", t!("cli.status.packages"));
-        for (package_name, (_, resolved_version, bumped_version)) in &bump_map {
+        for release in &plan.releases {
+            let bumped_by = if release.bumped_by.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", bumped_by_message(&release.bumped_by))
+            };
             println!(
-                "{:name_width$} {} → {}",
-                package_name.cyan().bold(),
-                resolved_version.to_string().yellow(),
-                bumped_version.to_string().green()
+                "{:name_width$} {} → {}{}",
+                release.name.cyan().bold(),
+                release.current_version.to_string().yellow(),
+                release.next_version.to_string().green(),
+                bumped_by.dimmed()
             );
         }
     }
 
-    if !warnings.is_empty() {
+    if !plan.notes.is_empty() {
+        println!("\n{}", t!("cli.status.pending_notes"));
+        for package_name in &plan.notes {
+            println!("{}", package_name.cyan().bold());
+        }
+    }
+
+    if !plan.warnings.is_empty() {
         println!("\n{}", t!("cli.status.pre_release_warning_header").yellow());
     }
-    for warning in warnings.iter() {
-        println!("{}", warning.yellow());
+    for warning in plan.warnings.iter() {
+        println!("{}", version::warning_message(warning).yellow());
     }
 
-    if !is_ci {
-        return Ok(());
+    if !plan.missing_changesets.is_empty() {
+        println!("\n{}", t!("cli.status.missing_changesets_header").yellow());
+        for package_name in &plan.missing_changesets {
+            println!("{}", package_name.cyan().bold());
+        }
     }
+}
 
-    let path = env::var("GITHUB_EVENT_PATH").context("no GITHUB_EVENT_PATH")?;
-    let event_data = std::fs::read_to_string(&path)?;
+pub(crate) async fn run(
+    status: &Status,
+    ctx: &Context,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    if !ctx.is_initialized() {
+        return Err(anyhow::anyhow!(t!("cli.not_initialized")));
+    };
 
-    log::debug!("GITHUB_EVENT_PATH: {}", &path);
-    log::debug!("GITHUB_EVENT_PATH data: {}", &event_data);
+    let is_ci = ctx.is_ci();
+    log::debug!("GitHub CI environment: {}", is_ci);
+
+    let config = ctx.config.as_ref().unwrap();
 
-    let event: GitHubEvent = serde_json::from_str(&event_data)?;
+    let changesets = resolver::get_changesets(ctx)?;
+    let mut plan = create_plan(ctx, &changesets)?;
+
+    if let Some(since) = &status.since {
+        let changed_files = ctx.get_changed_files(since)?;
+        log::debug!("Changed files since {}: {:?}", since, &changed_files);
+        plan.missing_changesets = ctx
+            .get_changed_packages(&changed_files)
+            .into_iter()
+            .filter(|package_name| {
+                !changesets
+                    .iter()
+                    .any(|c| c.packages.iter().any(|p| &p.name == package_name))
+            })
+            .collect::<Vec<_>>();
+    }
+
+    if format.is_json() {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+    } else {
+        print_plan(ctx, &plan);
+    }
+    let missing_packages = &plan.missing_changesets;
+    let warnings = &plan.warnings;
+
+    if !is_ci {
+        return ensure_changesets(status, missing_packages);
+    }
+
+    let event = read_github_event()?;
 
     let owner = &event.repository.owner.login;
     let repo_name = &event.repository.name;
     let pr_number = event.pull_request.number;
-    let head_ref = event.pull_request.head.ref_name;
-    let base_ref = event.pull_request.base.ref_name;
+    let head_ref = &event.pull_request.head.ref_name;
+    let base_ref = &event.pull_request.base.ref_name;
 
     log::debug!("owner: {}", owner);
     log::debug!("repo_name: {}", repo_name);
@@ -151,11 +279,19 @@ pub(crate) async fn run(status: &Status, ctx: &Context) -> anyhow::Result<()> {
     log::debug!("head_ref: {}", head_ref);
     log::debug!("base_ref: {}", base_ref);
 
+    if status.stamp {
+        let since = status
+            .since
+            .clone()
+            .unwrap_or_else(|| format!("origin/{base_ref}"));
+        stamp_changesets(ctx, &changesets, &event.pull_request, &since)?;
+    }
+
     let octocrab = Octocrab::builder()
         .personal_token(env::var("GITHUB_TOKEN")?)
         .build()?;
 
-    let is_matched = base_ref == config.branches.base && head_ref != config.branches.base;
+    let is_matched = base_ref == &config.branches.base && head_ref != &config.branches.base;
     if status.comment && is_matched {
         let issues = octocrab.issues(owner, repo_name);
 
@@ -174,21 +310,46 @@ pub(crate) async fn run(status: &Status, ctx: &Context) -> anyhow::Result<()> {
             .iter()
             .find(|c| c.user.login == "github-actions[bot]");
 
-        let markdown_table = bump_map
+        let markdown_table = plan
+            .releases
             .iter()
-            .map(|(k, (l, v, b))| format!("| {} | {} | {} | {} |", k, l, v, b))
+            .map(|r| {
+                let level = if r.bumped_by.is_empty() {
+                    r.level.to_string()
+                } else {
+                    format!("{} ({})", r.level, bumped_by_message(&r.bumped_by))
+                };
+                format!(
+                    "| {} | {} | {} | {} |",
+                    r.name, level, r.current_version, r.next_version
+                )
+            })
             .collect::<Vec<_>>()
             .join("\n");
         let warnings_section = if !warnings.is_empty() {
             let warnings_md = warnings
                 .iter()
-                .map(|w| format!("- {}", w))
+                .map(|w| format!("- {}", version::warning_message(w)))
                 .collect::<Vec<_>>()
                 .join("\n");
             format!("\n### Warnings\n\n{}", warnings_md)
         } else {
             String::new()
         };
+        let missing_section = if !missing_packages.is_empty() {
+            let missing_md = missing_packages
+                .iter()
+                .map(|p| format!("- `{}`", p))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                "\n### Missing Changesets\n\n\
+                The following packages have changes but are not mentioned in any changeset:\n\n{}",
+                missing_md
+            )
+        } else {
+            String::new()
+        };
         let comment_body = format!(
             "## Workspace change through: {}\n\n\
             {} changesets found\n\n\
@@ -198,11 +359,12 @@ pub(crate) async fn run(status: &Status, ctx: &Context) -> anyhow::Result<()> {
             | ------- | ---------- | --------------- | ------------ |\n\
             {}\n\
             </details>\n\
-            {}",
+            {}{}",
             &last_commit.sha,
             changesets.len(),
             &markdown_table,
             &warnings_section,
+            &missing_section,
         );
 
         if let Some(comment) = existing {
@@ -222,5 +384,61 @@ pub(crate) async fn run(status: &Status, ctx: &Context) -> anyhow::Result<()> {
         };
     }
 
-    Ok(())
+    ensure_changesets(status, missing_packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit, temp_repo};
+
+    #[test]
+    fn stamps_changesets_added_by_pull_request() {
+        let (root, repo) = temp_repo("stamp");
+        commit(
+            &repo,
+            &[(
+                ".changes/existing.md",
+                Some("---\na: \"patch:fix\"\n---\n\nExisting fix\n"),
+            )],
+            "base",
+        );
+        let changeset_root = root.join(".changes");
+        std::fs::write(
+            changeset_root.join("added.md"),
+            "---\na: \"minor:feat\"\n---\n\nAdded feature\n",
+        )
+        .unwrap();
+
+        let ctx = Context {
+            changeset_root: Some(changeset_root.clone()),
+            repo_root: Some(root.clone()),
+            git_repo: Some(repo),
+            ..Default::default()
+        };
+        let changesets = ["existing.md", "added.md"]
+            .map(|name| Changeset::parse_file(&changeset_root.join(name)).unwrap());
+        let pull_request = PullRequest {
+            number: 42,
+            head: Branch {
+                ref_name: "feature".to_string(),
+            },
+            base: Branch {
+                ref_name: "main".to_string(),
+            },
+            user: Some(RepoOwner {
+                login: "octocat".to_string(),
+            }),
+        };
+        stamp_changesets(&ctx, &changesets, &pull_request, "HEAD").unwrap();
+
+        let added = Changeset::parse_file(&changeset_root.join("added.md")).unwrap();
+        assert_eq!(added.pr, Some(42));
+        assert_eq!(added.authors, vec!["octocat".to_string()]);
+        assert_eq!(added.summary.trim(), "Added feature");
+        let existing = Changeset::parse_file(&changeset_root.join("existing.md")).unwrap();
+        assert_eq!(existing.pr, None);
+        assert!(existing.authors.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
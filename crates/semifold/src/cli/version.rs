@@ -1,20 +1,179 @@
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 use colored::Colorize;
 use rust_i18n::t;
-use semifold_changelog::{generate_changelog, utils::insert_changelog};
+use semifold_changelog::{
+    build_commit_index, compare_links, create_pr_resolver, generate_changelog,
+    generate_root_changelog, utils::insert_changelog,
+};
 use semifold_resolver::{
-    changeset::{BumpLevel, Changeset},
-    config::ResolverConfig,
+    changeset::{BumpLevel, ChangePackage, Changeset},
+    config::{ChangelogFormat, ResolverConfig},
     context::Context,
+    plan::{
+        PlanWarning, PlannedChangeset, ReleasePlan, SCHEMA_VERSION, create_plan, pending_changesets,
+    },
+    pre::PreState,
     resolver, utils,
 };
 
+use crate::cli::OutputFormat;
+
 #[derive(Parser, Debug)]
 pub(crate) struct Version {
     #[clap(long, help = t!("cli.version.flags.allow_dirty"))]
     allow_dirty: bool,
+    #[clap(
+        long,
+        value_name = "NAME",
+        num_args = 0..=1,
+        default_missing_value = "snapshot",
+        help = t!("cli.version.flags.snapshot")
+    )]
+    pub snapshot: Option<String>,
+    #[clap(long, value_name = "FILE", conflicts_with = "plan_in", help = t!("cli.version.flags.plan_out"))]
+    plan_out: Option<PathBuf>,
+    #[clap(long, value_name = "FILE", help = t!("cli.version.flags.plan_in"))]
+    plan_in: Option<PathBuf>,
+    #[clap(long, value_name = "FILE", help = t!("cli.version.flags.patch"))]
+    patch: Option<PathBuf>,
+}
+
+/// Localized message of a plan warning.
+pub(crate) fn warning_message(warning: &PlanWarning) -> String {
+    match warning {
+        PlanWarning::PreRelease { package } => t!(
+            "cli.status.pre_release_warning",
+            package = package.as_str().cyan()
+        ),
+        PlanWarning::ExplicitNotGreater {
+            package,
+            version,
+            current,
+        } => t!(
+            "cli.version.explicit_not_greater",
+            package = package.cyan(),
+            version = version.to_string().yellow(),
+            current = current.to_string().yellow()
+        ),
+    }
+    .to_string()
+}
+
+/// Check that a reviewed plan still applies to the workspace as it was
+/// planned, so that exactly the reviewed releases are applied.
+fn check_plan(ctx: &Context, plan: &ReleasePlan, changesets: &[Changeset]) -> anyhow::Result<()> {
+    if plan.schema_version != SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(t!(
+            "cli.version.plan_schema",
+            version = plan.schema_version,
+            expected = SCHEMA_VERSION
+        )));
+    }
+    let pending = pending_changesets(ctx, changesets)
+        .iter()
+        .map(PlannedChangeset::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    let names = |changesets: &[PlannedChangeset]| {
+        changesets
+            .iter()
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>()
+    };
+    if names(&plan.changesets) != names(&pending) {
+        return Err(anyhow::anyhow!(t!("cli.version.plan_changesets_changed")));
+    }
+    if let Some((planned, _)) = plan
+        .changesets
+        .iter()
+        .zip(&pending)
+        .find(|(planned, pending)| planned.hash != pending.hash)
+    {
+        return Err(anyhow::anyhow!(t!(
+            "cli.version.plan_changeset_edited",
+            name = &planned.name
+        )));
+    }
+    if plan.snapshot != ctx.snapshot {
+        return Err(anyhow::anyhow!(t!("cli.version.plan_snapshot_changed")));
+    }
+
+    let root = ctx.repo_root.as_ref().unwrap();
+    for release in &plan.releases {
+        let Some(package_config) = ctx.get_package_config(&release.name) else {
+            return Err(anyhow::anyhow!(t!(
+                "cli.version.plan_unknown_package",
+                package = &release.name
+            )));
+        };
+        let resolved_package = ctx
+            .create_resolver(package_config.resolver)
+            .resolve(root, package_config)?;
+        if resolved_package.version != release.current_version {
+            return Err(anyhow::anyhow!(t!(
+                "cli.version.plan_version_changed",
+                package = &release.name,
+                version = resolved_package.version.to_string(),
+                expected = release.current_version.to_string()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Packages of a changeset marked with `none` which are not released in this run.
+fn unreleased_notes(ctx: &Context, changeset: &Changeset) -> Vec<ChangePackage> {
+    let version_bumps = ctx.version_bumps.borrow();
+    changeset
+        .packages
+        .iter()
+        .filter(|p| p.level == BumpLevel::Unchanged && !version_bumps.contains_key(&p.name))
+        .cloned()
+        .collect()
+}
+
+/// Stage the removal of released changesets.
+///
+/// Notes of packages which were not released are kept, so that they are
+/// released with the next version of the package.
+fn consume_changesets(ctx: &Context, changesets: &[Changeset]) -> anyhow::Result<()> {
+    // Changesets derived from commits have no file to consume
+    for changeset in changesets.iter().filter(|c| c.path.is_some()) {
+        let notes = unreleased_notes(ctx, changeset);
+        if notes.is_empty() {
+            changeset.clean_in(&ctx.transaction);
+        } else {
+            let mut changeset = changeset.clone();
+            changeset.packages = notes;
+            changeset.commit_in(&ctx.transaction)?;
+        }
+    }
+    Ok(())
+}
+
+/// Lockfiles which post-version commands commonly update, looked up at the
+/// repository root and in each package.
+const LOCKFILES: [&str; 10] = [
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "pnpm-lock.yaml",
+    "yarn.lock",
+    "bun.lock",
+    "bun.lockb",
+    "poetry.lock",
+    "uv.lock",
+    "pdm.lock",
+];
+
+/// Paths of the lockfiles of the workspace, which may not exist yet.
+fn lockfiles(ctx: &Context) -> Vec<PathBuf> {
+    let root = ctx.repo_root.as_ref().unwrap();
+    std::iter::once(root.clone())
+        .chain(ctx.get_packages().iter().map(|(_, c)| root.join(&c.path)))
+        .flat_map(|dir| LOCKFILES.map(|name| dir.join(name)))
+        .collect()
 }
 
 pub(crate) fn post_version(ctx: &Context) -> anyhow::Result<()> {
@@ -60,90 +219,354 @@ pub(crate) fn post_version(ctx: &Context) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Render staged writes as a unified diff against the files on disk, with
+/// paths relative to `root`.
+fn staged_diff(root: &Path, staged: &[(PathBuf, Option<String>)]) -> anyhow::Result<String> {
+    let mut diff = String::new();
+    for (path, content) in staged {
+        let original = std::fs::read_to_string(path).ok();
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let mut patch = git2::Patch::from_buffers(
+            original.as_deref().unwrap_or_default().as_bytes(),
+            Some(relative),
+            content.as_deref().unwrap_or_default().as_bytes(),
+            Some(relative),
+            None,
+        )?;
+        let buf = patch.to_buf()?;
+        let text = buf.as_str().unwrap_or_default();
+        if original.is_some() && content.is_some() {
+            diff.push_str(text);
+            continue;
+        }
+
+        // Diffing buffers always yields a modification, so the header is
+        // rewritten for added and removed files to keep the patch applicable
+        let (hunks, header) = match text.find("\n@@") {
+            Some(pos) => (&text[pos + 1..], &text[..pos + 1]),
+            None => ("", text),
+        };
+        let (old_id, new_id) = header
+            .lines()
+            .find_map(|line| line.strip_prefix("index "))
+            .and_then(|index| index.split_whitespace().next())
+            .and_then(|ids| ids.split_once(".."))
+            .unwrap_or_default();
+        let name = relative.display();
+        diff.push_str(&format!("diff --git a/{name} b/{name}\n"));
+        if original.is_none() {
+            diff.push_str(&format!(
+                "new file mode 100644\nindex 0000000..{new_id}\n--- /dev/null\n+++ b/{name}\n"
+            ));
+        } else {
+            diff.push_str(&format!(
+                "deleted file mode 100644\nindex {old_id}..0000000\n--- a/{name}\n+++ /dev/null\n"
+            ));
+        }
+        diff.push_str(hunks);
+    }
+    Ok(diff)
+}
+
+fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        let line = if [
+            "diff ",
+            "index ",
+            "new file",
+            "deleted file",
+            "--- ",
+            "+++ ",
+        ]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+        {
+            line.bold()
+        } else if line.starts_with("@@") {
+            line.cyan()
+        } else if line.starts_with('+') {
+            line.green()
+        } else if line.starts_with('-') {
+            line.red()
+        } else {
+            line.normal()
+        };
+        println!("{line}");
+    }
+}
+
+/// Apply a release plan of the pending changesets, returning the applied
+/// plan with the generated changelogs.
 pub(crate) async fn version(
     ctx: &Context,
     changesets: &[Changeset],
-) -> anyhow::Result<HashMap<String, String>> {
+    plan: &ReleasePlan,
+) -> anyhow::Result<ReleasePlan> {
     let config = ctx.config.as_ref().unwrap();
     let root = ctx.repo_root.as_ref().unwrap();
     let Some(repo) = ctx.git_repo.as_ref() else {
         return Err(anyhow::anyhow!(t!("cli.version.no_git_repo")));
     };
-    let mut changelogs_map = HashMap::new();
-
-    let mut sorted_packages = config.packages.clone().into_iter().collect::<Vec<_>>();
-    for resolver in config.resolver.keys() {
-        ctx.create_resolver(*resolver)
-            .sort_packages(root, &mut sorted_packages)?;
+    let pending = pending_changesets(ctx, changesets);
+    let mut plan = plan.clone();
+    plan.dry_run = ctx.dry_run;
+    for warning in &plan.warnings {
+        log::warn!("{}", warning_message(warning));
+    }
+    // Snapshot releases never write changelogs, so the history is not needed
+    let commit_index = if ctx.is_snapshot() {
+        Default::default()
+    } else {
+        build_commit_index(ctx, repo, &pending)?
+    };
+    let mut pr_resolver = create_pr_resolver(ctx);
+    if let Some(resolver) = pr_resolver.as_mut()
+        && let Err(e) = resolver.prefetch(commit_index.commits()).await
+    {
+        log::warn!(
+            "{}",
+            t!("cli.version.pr_lookup_failed", reason = e.to_string())
+        );
+        pr_resolver = None;
     }
-    for (package_name, package_config) in &sorted_packages {
-        log::debug!("Processing package: {}", package_name);
-        let mut resolver = ctx.create_resolver(package_config.resolver);
-        let resolved_package = resolver.resolve(root, package_config)?;
-        let level = utils::get_bump_level(changesets, package_name);
 
-        // Skip unchanged packages
-        if matches!(level, BumpLevel::Unchanged) {
+    for package_name in config.packages.keys() {
+        if plan.get_release(package_name).is_none() {
             log::warn!(
                 "{}",
                 t!("cli.version.unchanged", package = package_name.cyan())
             );
-            continue;
         }
+    }
+    for release in &mut plan.releases {
+        let package_name = &release.name;
+        log::debug!("Processing package: {}", package_name);
+        let Some(package_config) = ctx.get_package_config(package_name) else {
+            return Err(anyhow::anyhow!(t!(
+                "cli.version.plan_unknown_package",
+                package = package_name
+            )));
+        };
+        let mut resolver = ctx.create_resolver(package_config.resolver);
+        let resolved_package = resolver.resolve(root, package_config)?;
+        let bumped_version = &release.next_version;
 
-        let mut bumped_version = resolved_package.version.clone();
-        utils::bump_version(&mut bumped_version, level, &package_config.version_mode)?;
-        resolver.bump(ctx, root, &resolved_package, &bumped_version)?;
+        resolver.bump(ctx, root, &resolved_package, bumped_version)?;
         ctx.version_bumps
             .borrow_mut()
             .entry(package_name.clone())
             .or_insert(bumped_version.clone());
 
+        // Snapshot releases never write changelogs
+        if ctx.is_snapshot() {
+            log::info!(
+                "{}",
+                t!(
+                    "cli.version.snapshot",
+                    package = package_name.cyan(),
+                    version = bumped_version.to_string().green()
+                )
+            );
+            continue;
+        }
+
         let changelog = generate_changelog(
             ctx,
-            repo,
-            changesets,
+            &commit_index,
+            pr_resolver.as_mut(),
+            &pending,
             package_name,
             &bumped_version.to_string(),
         )
         .await?;
-        changelogs_map.insert(package_name.to_string(), changelog.clone());
-
         log::debug!("changelog for {}:\n{}", package_name, changelog);
 
-        if !ctx.dry_run {
-            insert_changelog(
-                root.join(&package_config.path).join("CHANGELOG.md"),
-                &changelog,
-            )
-            .await?;
+        insert_changelog(
+            &ctx.transaction,
+            root.join(&package_config.path).join("CHANGELOG.md"),
+            &changelog,
+            config.changelog.format,
+            compare_links(ctx, package_name).as_ref(),
+        )
+        .await?;
+        release.changelog = Some(changelog);
+    }
+
+    let versions = plan
+        .releases
+        .iter()
+        .map(|r| r.next_version.to_string())
+        .collect::<Vec<_>>();
+    // Packages with changelogs in release order
+    let releases = plan
+        .releases
+        .iter()
+        .zip(&versions)
+        .filter_map(|(r, version)| {
+            Some((r.name.as_str(), version.as_str(), r.changelog.as_deref()?))
+        })
+        .collect::<Vec<_>>();
+    if let Some(root_changelog) = &config.changelog.root
+        && !releases.is_empty()
+    {
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let changelog = generate_root_changelog(&releases, &date);
+        log::debug!("root changelog:\n{}", changelog);
+
+        insert_changelog(
+            &ctx.transaction,
+            root.join(root_changelog),
+            &changelog,
+            ChangelogFormat::Default,
+            None,
+        )
+        .await?;
+    }
+
+    if !ctx.is_snapshot() {
+        let changeset_root = ctx.changeset_root.as_ref().unwrap();
+        match &ctx.pre_state {
+            // Keep changesets in pre-release mode to aggregate them on exit
+            Some(pre_state) if pre_state.is_pre() => {
+                let mut pre_state = pre_state.clone();
+                let version_bumps = ctx.version_bumps.borrow();
+                pre_state.record_released(&pending, |name| version_bumps.contains_key(name));
+                pre_state.save_in(&ctx.transaction, changeset_root)?;
+            }
+            Some(_) => {
+                consume_changesets(ctx, changesets)?;
+                PreState::remove_in(&ctx.transaction, changeset_root);
+            }
+            None => consume_changesets(ctx, changesets)?,
         }
     }
 
-    if !ctx.dry_run {
-        changesets.iter().try_for_each(|c| c.clean())?;
+    // Nothing is written until every package is versioned, the writes of a
+    // dry run are left staged for previews
+    if ctx.dry_run {
+        post_version(ctx)?;
+        return Ok(plan);
+    }
+    let mut committed = ctx.transaction.commit()?;
+    let count = committed.len();
+    // Lockfiles updated by post-version commands are restored on failure too
+    committed.track(lockfiles(ctx))?;
+    if let Err(e) = post_version(ctx) {
+        log::warn!("{}", t!("cli.version.rolled_back", count = count));
+        committed.rollback()?;
+        return Err(e);
     }
-    post_version(ctx)?;
 
-    Ok(changelogs_map)
+    Ok(plan)
 }
 
-pub(crate) async fn run(opts: &Version, ctx: &Context) -> anyhow::Result<()> {
+pub(crate) async fn run(opts: &Version, ctx: &Context, format: OutputFormat) -> anyhow::Result<()> {
     if !ctx.is_initialized() {
         return Err(anyhow::anyhow!(t!("cli.not_initialized")));
     };
 
+    let changesets = resolver::get_changesets(ctx)?;
+    let plan = match &opts.plan_in {
+        Some(path) => {
+            let plan: ReleasePlan = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+            check_plan(ctx, &plan, &changesets)?;
+            plan
+        }
+        None => create_plan(ctx, &changesets)?,
+    };
+
+    // Only write the plan, to be reviewed and applied with `--plan-in`
+    if let Some(path) = &opts.plan_out {
+        std::fs::write(path, serde_json::to_string_pretty(&plan)?)?;
+        log::info!(
+            "{}",
+            t!(
+                "cli.version.plan_written",
+                count = plan.releases.len(),
+                path = path.display().to_string().cyan()
+            )
+        );
+        if format.is_json() {
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+        }
+        return Ok(());
+    }
+
     if !opts.allow_dirty && !ctx.is_git_repo_clean() {
         return Err(anyhow::anyhow!(t!("cli.dirty_repo")));
     }
+    if opts.patch.is_some() && !ctx.dry_run {
+        return Err(anyhow::anyhow!(t!("cli.version.patch_requires_dry_run")));
+    }
 
-    let changesets = resolver::get_changesets(ctx)?;
-    if changesets.is_empty() {
+    let mut plan = if plan.changesets.is_empty() {
         log::warn!("{}", t!("cli.version.empty_changesets"));
-        return Ok(());
+        plan
+    } else {
+        version(ctx, &changesets, &plan).await?
+    };
+
+    if ctx.dry_run {
+        let root = ctx.repo_root.clone().unwrap_or(std::env::current_dir()?);
+        let diff = staged_diff(&root, &ctx.transaction.take())?;
+        if let Some(path) = &opts.patch {
+            std::fs::write(path, &diff)?;
+            log::info!(
+                "{}",
+                t!(
+                    "cli.version.patch_written",
+                    path = path.display().to_string().cyan()
+                )
+            );
+        } else if format.is_json() {
+            plan.diff = Some(diff);
+        } else {
+            print_diff(&diff);
+        }
     }
 
-    version(ctx, &changesets).await?;
+    if format.is_json() {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_repo;
+
+    #[test]
+    fn renders_applicable_staged_diff() {
+        let (root, repo) = temp_repo("staged-diff");
+        std::fs::write(root.join("modified.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(root.join("removed.txt"), "gone\n").unwrap();
+
+        let staged = vec![
+            (root.join("modified.txt"), Some("one\nthree\n".to_string())),
+            (root.join("added.txt"), Some("new\n".to_string())),
+            (root.join("removed.txt"), None),
+        ];
+        let diff = staged_diff(&root, &staged).unwrap();
+        assert!(diff.contains("diff --git a/modified.txt b/modified.txt\n"));
+        assert!(diff.contains("-two\n+three\n"));
+        assert!(diff.contains("new file mode 100644\n"));
+        assert!(diff.contains("--- /dev/null\n+++ b/added.txt\n"));
+        assert!(diff.contains("deleted file mode 100644\n"));
+        assert!(diff.contains("--- a/removed.txt\n+++ /dev/null\n"));
+
+        let patch = git2::Diff::from_buffer(diff.as_bytes()).unwrap();
+        let mut options = git2::ApplyOptions::new();
+        options.check(true);
+        repo.apply(&patch, git2::ApplyLocation::WorkDir, Some(&mut options))
+            .unwrap();
+        repo.apply(&patch, git2::ApplyLocation::WorkDir, None)
+            .unwrap();
+        let read = |name: &str| std::fs::read_to_string(root.join(name)).ok();
+        assert_eq!(read("modified.txt").as_deref(), Some("one\nthree\n"));
+        assert_eq!(read("added.txt").as_deref(), Some("new\n"));
+        assert_eq!(read("removed.txt"), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
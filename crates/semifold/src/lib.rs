@@ -4,6 +4,8 @@ use semifold_resolver::context;
 
 pub mod cli;
 pub mod logger;
+#[cfg(test)]
+mod test_utils;
 pub mod utils;
 
 use cli::{Cli, Commands};
@@ -13,11 +15,17 @@ rust_i18n::i18n!("locales", fallback = "en");
 pub fn run() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    if cli.debug {
-        logger::setup_logger(LevelFilter::Debug)?;
+    let level = if cli.debug {
+        LevelFilter::Debug
     } else {
-        logger::setup_logger(LevelFilter::Info)?;
+        LevelFilter::Info
+    };
+    if cli.format.is_json() {
+        // Strings embedded in JSON documents must not contain color codes
+        colored::control::set_override(false);
     }
+    // Keep stdout for the JSON document only
+    logger::setup_logger(level, cli.format.is_json())?;
 
     log::debug!("Parsed CLI arguments: {:?}", &cli);
 
@@ -29,10 +37,23 @@ pub fn run() -> anyhow::Result<()> {
     match &cli.command {
         Some(Commands::Commit(commit)) => cli::commit::run(commit, &ctx)?,
         Some(Commands::Init(init)) => cli::init::run(init, &ctx)?,
-        Some(Commands::Version(version)) => utils::run_async(cli::version::run(version, &ctx))?,
-        Some(Commands::Publish(publish)) => utils::run_async(cli::publish::run(publish, &ctx))?,
+        Some(Commands::Version(version)) => {
+            ctx.snapshot(version.snapshot.clone());
+            utils::run_async(cli::version::run(version, &ctx, cli.format))?
+        }
+        Some(Commands::Publish(publish)) => {
+            utils::run_async(cli::publish::run(publish, &ctx, cli.format))?
+        }
         Some(Commands::CI(ci)) => utils::run_async(cli::ci::run(ci, &ctx))?,
-        Some(Commands::Status(status)) => utils::run_async(cli::status::run(status, &ctx))?,
+        Some(Commands::Status(status)) => {
+            utils::run_async(cli::status::run(status, &ctx, cli.format))?
+        }
+        Some(Commands::Check(check)) => cli::check::run(check, &ctx)?,
+        Some(Commands::Changeset(changeset)) => cli::changeset::run(changeset, &ctx, cli.format)?,
+        Some(Commands::Pre(pre)) => cli::pre::run(pre, &ctx)?,
+        Some(Commands::Changelog(changelog)) => {
+            utils::run_async(cli::changelog::run(changelog, &ctx, cli.format))?
+        }
         None => {}
     }
 
@@ -10,7 +10,14 @@ fn format_level(level: log::Level) -> ColoredString {
     }
 }
 
-pub fn setup_logger(level: log::LevelFilter) -> Result<(), fern::InitError> {
+/// Log to stdout, or to stderr when `stderr` is set so that stdout only
+/// contains machine-readable output.
+pub fn setup_logger(level: log::LevelFilter, stderr: bool) -> Result<(), fern::InitError> {
+    let output: fern::Output = if stderr {
+        std::io::stderr().into()
+    } else {
+        std::io::stdout().into()
+    };
     fern::Dispatch::new()
         .format(move |out, message, record| {
             if matches!(record.level(), log::Level::Debug) {
@@ -29,7 +36,7 @@ pub fn setup_logger(level: log::LevelFilter) -> Result<(), fern::InitError> {
             }
         })
         .level(level)
-        .chain(std::io::stdout())
+        .chain(output)
         .apply()?;
     Ok(())
 }